@@ -0,0 +1,259 @@
+//! Binary blob values.
+//!
+//! A blob is split into fixed-size Chunk memos which are content-addressed, and thus deduplicated within a slab. The
+//! chunks are gathered into a tree of ChunkTree memos, no more than `CHUNK_FANOUT` to a node, and a Blob memo in the
+//! causal history of the entity binds the top tier of that tree to a key. Chunks are only retrieved as they are read,
+//! so that seeking within a large blob fetches only the chunks, and the nodes above them, which are needed.
+
+use crate::{
+    entity::Entity,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    slab::{
+        EntityType,
+        Memo,
+        MemoBody,
+        MemoRef,
+        SlabHandle,
+    },
+};
+
+use futures::{
+    future::{
+        BoxFuture,
+        FutureExt,
+    },
+    io::{
+        AsyncRead,
+        AsyncSeek,
+        SeekFrom,
+    },
+    task::{
+        Context,
+        Poll,
+    },
+    StreamExt,
+};
+use std::{
+    fmt,
+    io,
+    pin::Pin,
+};
+
+/// Chunks are sized such that a serialized Chunk memo comfortably fits in a single datagram
+pub const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The most memorefs listed by a Blob or ChunkTree memo, such that these too fit in a single datagram
+pub const CHUNK_FANOUT: usize = 64;
+
+impl Entity {
+    /// Store binary data under the given key
+    pub async fn set_blob(&mut self, key: &str, data: &[u8]) -> Result<(), WriteError> {
+        self.context.check_writable()?;
+
+        if let EntityType::Custom(type_id) = self.id.stype {
+            self.context.require_schema(type_id).await?.validate_blob(key)?;
+        }
+
+        let slab = &self.context.slab;
+
+        // Gather the chunks into tiers of ChunkTree memos until the top tier is small enough for the Blob memo
+        let mut tier: Vec<MemoRef> = data.chunks(CHUNK_SIZE).map(|c| slab.new_chunk_memo(c.to_vec())).collect();
+        while tier.len() > CHUNK_FANOUT {
+            tier = tier.chunks(CHUNK_FANOUT).map(|nodes| slab.new_chunk_tree_memo(nodes.to_vec())).collect();
+        }
+
+        let body = MemoBody::Blob { k: key.to_string(),
+                                    l: data.len() as u64,
+                                    s: CHUNK_SIZE as u32,
+                                    f: CHUNK_FANOUT as u32,
+                                    c: Head::Anonymous { owning_slab_id: slab.my_ref.slab_id,
+                                                         head:           tier, }, };
+
+        let parents = std::mem::replace(&mut self.head, Head::Null);
+        self.head = slab.new_memo(Some(self.id), parents, body).to_head();
+
+        // Update our indices before returning to ensure that subsequence queries against this context are
+        // self-consistent
+        self.context.update_indices(self.id, &self.head).await?;
+
+        Ok(())
+    }
+
+    /// Retrieve a reader for the blob stored under the given key. No chunks are fetched until they are read.
+    pub async fn get_blob(&mut self, key: &str) -> Result<Option<BlobReader>, RetrieveError> {
+        self.context.mut_update_record_head_for_consistency(&mut self.head).await?;
+
+        // Blob memos are not subsumed by materialized memos, so we may have to consider the whole causal history
+        let mut memostream = self.head.causal_memo_stream(self.context.slab.clone());
+        while let Some(memo) = memostream.next().await {
+            if let MemoBody::Blob { ref k, l, s, f, ref c } = memo?.body {
+                if k == key {
+                    if s == 0 || f < 2 {
+                        return Err(RetrieveError::Malformed);
+                    }
+
+                    // The number of ChunkTree tiers is the fewest which can hold all of the chunks
+                    let chunk_count = l.div_ceil(s as u64);
+                    let mut capacity = f as u64;
+                    let mut tiers = 0;
+                    while capacity < chunk_count {
+                        capacity *= f as u64;
+                        tiers += 1;
+                    }
+
+                    return Ok(Some(BlobReader { slab: self.context.slab.clone(),
+                                                top: c.to_vec(),
+                                                tiers,
+                                                fanout: f as u64,
+                                                len: l,
+                                                chunk_size: s as u64,
+                                                position: 0,
+                                                current: None,
+                                                fetching: None }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct BlobReader {
+    slab:       SlabHandle,
+    top:        Vec<MemoRef>,
+    tiers:      u32,
+    fanout:     u64,
+    len:        u64,
+    chunk_size: u64,
+    position:   u64,
+    current:    Option<(u64, Memo)>,
+    fetching:   Option<(u64, BoxFuture<'static, io::Result<Memo>>)>,
+}
+
+impl BlobReader {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Descend from the top tier through the ChunkTree memos to the chunk with the given index
+    async fn fetch_chunk(slab: SlabHandle, mut memoref: MemoRef, tiers: u32, fanout: u64, index: u64) -> io::Result<Memo> {
+        let get_memo = |memoref: MemoRef| {
+            let slab = slab.clone();
+            async move { memoref.get_memo(slab).await.map_err(|e| io::Error::other(format!("{:?}", e))) }
+        };
+
+        for tier in (0..tiers).rev() {
+            memoref = match get_memo(memoref).await?.body {
+                MemoBody::ChunkTree(ref head) => {
+                    let position = (index / fanout.pow(tier)) % fanout;
+                    head.to_vec()
+                        .get(position as usize)
+                        .cloned()
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing chunk"))?
+                },
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid chunk tree")),
+            };
+        }
+
+        get_memo(memoref).await
+    }
+
+    fn poll_chunk(&mut self, cx: &mut Context, index: u64) -> Poll<io::Result<()>> {
+        if let Some((current, _)) = self.current {
+            if current == index {
+                return Poll::Ready(Ok(()));
+            }
+        }
+
+        match self.fetching {
+            Some((fetching, _)) if fetching == index => {},
+            _ => {
+                let memoref = self.top
+                                  .get((index / self.fanout.pow(self.tiers)) as usize)
+                                  .cloned()
+                                  .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing chunk"))?;
+
+                let fetch = Self::fetch_chunk(self.slab.clone(), memoref, self.tiers, self.fanout, index);
+                self.fetching = Some((index, fetch.boxed()));
+            },
+        }
+
+        let (_, fut) = self.fetching.as_mut().unwrap();
+        match fut.as_mut().poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => {
+                self.fetching = None;
+                self.current = Some((index, result?));
+
+                Poll::Ready(Ok(()))
+            },
+        }
+    }
+}
+
+impl AsyncRead for BlobReader {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if self.position >= self.len || buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let index = self.position / self.chunk_size;
+        match self.poll_chunk(cx, index) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(result) => result?,
+        }
+
+        let offset = (self.position - index * self.chunk_size) as usize;
+        let read = match self.current {
+            Some((_, ref memo)) => {
+                match memo.body {
+                    MemoBody::Chunk(ref data) if offset < data.len() => {
+                        let read = buf.len().min(data.len() - offset);
+                        buf[..read].copy_from_slice(&data[offset..offset + read]);
+                        read
+                    },
+                    _ => return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, "invalid chunk"))),
+                }
+            },
+            None => unreachable!(),
+        };
+
+        self.position += read as u64;
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl AsyncSeek for BlobReader {
+    fn poll_seek(mut self: Pin<&mut Self>, _cx: &mut Context, pos: SeekFrom) -> Poll<io::Result<u64>> {
+        let position = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => self.len as i64 + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+        };
+
+        if position < 0 {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative position")));
+        }
+
+        self.position = position as u64;
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+impl fmt::Debug for BlobReader {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("BlobReader")
+           .field("len", &self.len)
+           .field("tiers", &self.tiers)
+           .field("position", &self.position)
+           .finish()
+    }
+}
@@ -0,0 +1,220 @@
+use super::{
+    CompactionStats,
+    Context,
+    ContextInner,
+};
+
+use futures::future::RemoteHandle;
+use std::{
+    sync::Weak,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use timer::Delay;
+use tracing::{
+    debug,
+    warn,
+};
+
+/// When a context should compact its stash of its own accord, rather than waiting for [`Context::compact`] to be
+/// called. Compaction is triggered by whichever threshold is reached first, and thresholds which are not set never
+/// trigger it. A policy with no thresholds at all disables background compaction, which is the default.
+///
+/// ```
+/// # use unbase::{Network, Slab, context::CompactionPolicy};
+/// # use std::time::Duration;
+/// # let net = Network::create_new_system();
+/// # let slab = Slab::new(&net);
+/// let context = slab.create_context();
+/// context.set_compaction_policy(CompactionPolicy::new().max_heads(64).interval(Duration::from_secs(30)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactionPolicy {
+    max_heads:  Option<usize>,
+    interval:   Option<Duration>,
+    max_writes: Option<usize>,
+    exchange:   bool,
+    poll:       Duration,
+}
+
+/// What background and foreground compactions of a context have done so far
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompactionMetrics {
+    /// The number of compactions, whether triggered by the policy or not
+    pub runs:            usize,
+    /// The number of compactions triggered by the policy
+    pub background_runs: usize,
+    /// The number of background compactions which failed
+    pub failures:        usize,
+    /// The total number of heads removed from the stash
+    pub heads_pruned:    usize,
+    /// The total number of Edge memos issued
+    pub memos_issued:    usize,
+    /// Writes applied to the stash since the last compaction, whether made in this context or received from other slabs
+    pub pending_writes:  usize,
+    pub last:            Option<CompactionStats>,
+}
+
+impl CompactionPolicy {
+    /// A policy which never triggers compaction
+    pub fn new() -> Self {
+        CompactionPolicy { max_heads:  None,
+                           interval:   None,
+                           max_writes: None,
+                           exchange:   false,
+                           poll:       Duration::from_millis(250), }
+    }
+
+    /// Compact once the stash holds this many heads
+    pub fn max_heads(mut self, max_heads: usize) -> Self {
+        self.max_heads = Some(max_heads);
+        self
+    }
+
+    /// Compact when this much time has passed since the last compaction, provided there have been writes since
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Compact once this many writes have been applied to the stash since the last compaction. Index heads received
+    /// from other slabs count as writes, just as those made in this context do.
+    pub fn max_writes(mut self, max_writes: usize) -> Self {
+        self.max_writes = Some(max_writes);
+        self
+    }
+
+    /// Send the context to peer slabs after each background compaction, as [`Context::send_context`] does
+    pub fn exchange(mut self, exchange: bool) -> Self {
+        self.exchange = exchange;
+        self
+    }
+
+    /// How often the thresholds are checked. Defaults to 250ms.
+    pub fn poll(mut self, poll: Duration) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_heads.is_some() || self.interval.is_some() || self.max_writes.is_some()
+    }
+
+    fn is_due(&self, heads: usize, writes: usize, since: Duration) -> bool {
+        self.max_heads.is_some_and(|max_heads| heads >= max_heads)
+        || self.max_writes.is_some_and(|max_writes| writes >= max_writes)
+        || (writes > 0 && self.interval.is_some_and(|interval| since >= interval))
+    }
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The compaction bookkeeping of a context
+pub(super) struct Compaction {
+    policy:  CompactionPolicy,
+    metrics: CompactionMetrics,
+    last:    Instant,
+    /// Dropping the handle stops the background task
+    task:    Option<RemoteHandle<()>>,
+}
+
+impl Compaction {
+    pub(super) fn new() -> Self {
+        Compaction { policy:  CompactionPolicy::new(),
+                     metrics: CompactionMetrics::default(),
+                     last:    Instant::now(),
+                     task:    None, }
+    }
+
+    pub(super) fn record_write(&mut self) {
+        self.metrics.pending_writes += 1;
+    }
+
+    pub(super) fn record(&mut self, stats: CompactionStats) {
+        self.metrics.runs += 1;
+        self.metrics.heads_pruned += stats.heads_before.saturating_sub(stats.heads_after);
+        self.metrics.memos_issued += stats.memos_issued;
+        self.metrics.pending_writes = 0;
+        self.metrics.last = Some(stats);
+        self.last = Instant::now();
+    }
+}
+
+impl Context {
+    /// Compact the stash in the background according to the given policy, in place of any previous policy. The
+    /// background task only ever holds the stash for as long as [`Context::compact`] would, so foreground reads and
+    /// writes carry on as usual while it runs. It stops when the policy is replaced or the context is dropped.
+    pub fn set_compaction_policy(&self, policy: CompactionPolicy) {
+        let task = if policy.is_enabled() {
+            let weak = std::sync::Arc::downgrade(&self.0);
+            Some(crate::util::task::spawn_with_handle(compactor(weak, policy.poll)))
+        } else {
+            None
+        };
+
+        let mut compaction = self.compaction.lock().unwrap();
+        compaction.policy = policy;
+        // Replacing the handle drops the previous task, if any
+        compaction.task = task;
+    }
+
+    pub fn compaction_policy(&self) -> CompactionPolicy {
+        self.compaction.lock().unwrap().policy.clone()
+    }
+
+    pub fn compaction_metrics(&self) -> CompactionMetrics {
+        self.compaction.lock().unwrap().metrics.clone()
+    }
+
+    /// Whether the policy calls for a compaction, and if so whether to exchange the context afterward
+    fn compaction_due(&self) -> Option<bool> {
+        let (heads, _) = self.stash.size();
+
+        let compaction = self.compaction.lock().unwrap();
+        let policy = &compaction.policy;
+
+        if policy.is_due(heads, compaction.metrics.pending_writes, compaction.last.elapsed()) {
+            Some(policy.exchange)
+        } else {
+            None
+        }
+    }
+}
+
+async fn compactor(weak: Weak<ContextInner>, poll: Duration) {
+    loop {
+        Delay::new(poll).await;
+
+        // Don't keep the context alive between checks
+        let context = match weak.upgrade() {
+            Some(inner) => Context(inner),
+            None => return,
+        };
+
+        let exchange = match context.compaction_due() {
+            Some(exchange) => exchange,
+            None => continue,
+        };
+
+        match context.compact().await {
+            Ok(stats) => {
+                debug!("Background compaction {:?}", stats);
+                context.compaction.lock().unwrap().metrics.background_runs += 1;
+
+                if exchange {
+                    context.send_stash();
+                }
+            },
+            Err(e) => {
+                warn!("Background compaction failed: {:?}", e);
+                context.compaction.lock().unwrap().metrics.failures += 1;
+            },
+        }
+    }
+}
@@ -1,14 +1,49 @@
+mod compaction;
+mod persist;
+mod scan;
 pub mod stash;
+mod token;
+
+pub use self::compaction::{
+    CompactionMetrics,
+    CompactionPolicy,
+};
+pub use self::scan::{
+    Scan,
+    ScanToken,
+};
+pub use self::token::CausalToken;
 
 use crate::{
     entity::Entity,
     error::{
         InvalidHead,
         RetrieveError,
+        SchemaViolation,
         WriteError,
     },
     head::Head,
-    index::IndexFixed,
+    index::{
+        read_catalog,
+        Declared,
+        IndexComposite,
+        IndexFixed,
+        IndexFullText,
+        IndexKind,
+        IndexOrdered,
+        IndexSecondary,
+        IndexShape,
+        IndexTyped,
+        SearchHit,
+    },
+    schema::{
+        migration::{
+            self,
+            VERSION_KEY,
+        },
+        Migration,
+        Schema,
+    },
     slab::{
         EdgeLink,
         EdgeSet,
@@ -19,10 +54,14 @@ use crate::{
         RelationSet,
         SlabHandle,
         SlotId,
+        TypeId,
     },
 };
 
-use self::stash::Stash;
+use self::{
+    compaction::Compaction,
+    stash::Stash,
+};
 use timer::Delay;
 
 use std::{
@@ -30,6 +69,10 @@ use std::{
     fmt,
     ops::Deref,
     sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
         Arc,
         Mutex,
     },
@@ -46,21 +89,91 @@ use futures::{
 };
 
 use tracing::{
+    debug,
     span,
+    warn,
     Level,
 };
 
+/// The most heads sent in one context exchange memo. Larger stashes are split across several, such that the recipient
+/// may apply each before its queue of index heads fills up
+const MAX_EXCHANGE_HEADS: usize = 100;
+
+/// Present in the values of a root index node which has been superseded by [`Context::reindex`]
+const ROOT_INDEX_SUCCESSOR_KEY: &str = "successor";
+
 #[derive(Clone)]
 pub struct Context(Arc<ContextInner>);
 
+/// The size of the stash before and after a [`Context::compact`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// The number of entity heads in the stash
+    pub heads_before:    usize,
+    pub heads_after:     usize,
+    /// The number of memorefs across all of those heads
+    pub memorefs_before: usize,
+    pub memorefs_after:  usize,
+    /// The number of Edge memos issued to parent nodes
+    pub memos_issued:    usize,
+}
+
 pub struct ContextInner {
     pub slab:            SlabHandle,
     pub root_index_node: Arc<Mutex<Option<Head>>>,
-    _applier:            RemoteHandle<()>,
+    /// The number of migrations to follow from the root index seed, and the shape of the resulting root index
+    root_index_resolved: Mutex<Option<(usize, IndexShape)>>,
+    /// Snapshots have no applier, such that their stash stays as it was
+    _applier:            Option<RemoteHandle<()>>,
     stash:               Stash,
+    read_only:           bool,
+    migrations:          Mutex<HashMap<TypeId, Vec<Migration>>>,
+    /// Shared with the applier, which counts the heads it applies as writes
+    compaction:          Arc<Mutex<Compaction>>,
+    index_catalog:       Arc<Mutex<CatalogCache>>,
+    index_observers:     Arc<Mutex<Vec<IndexObserver>>>,
     // pathology:  Option<Box<Fn(String)>> // Something is wrong here, causing compile to fail with a recursion error
 }
 
+/// The index catalog as this context last read it. See [`Context::index_catalog`]
+#[derive(Clone)]
+enum CatalogCache {
+    /// Not yet looked up in the root index
+    Unknown,
+    /// Not found in the root index, as of the last head to arrive from elsewhere
+    Absent,
+    Read { head: Head, declared: Arc<Vec<Declared>> },
+}
+
+impl CatalogCache {
+    /// Heads which arrive from other contexts may lead to a catalog which was not found before
+    fn reconsider(&mut self) {
+        if let CatalogCache::Absent = self {
+            *self = CatalogCache::Unknown;
+        }
+    }
+}
+
+/// A subscriber to the index heads applied to a context. See [`Context::observe_applied`]
+struct IndexObserver {
+    tx:     mpsc::Sender<Head>,
+    lagged: Arc<AtomicBool>,
+}
+
+/// Heads are dropped rather than waited for should an observer fall behind, and the observer is told as much
+fn notify_index_observers(observers: &Mutex<Vec<IndexObserver>>, head: &Head) {
+    observers.lock().unwrap().retain_mut(|observer| {
+                                 match observer.tx.try_send(head.clone()) {
+                                     Ok(()) => true,
+                                     Err(e) if e.is_full() => {
+                                         observer.lagged.store(true, Ordering::SeqCst);
+                                         true
+                                     },
+                                     Err(_) => false,
+                                 }
+                             });
+}
+
 impl Deref for Context {
     type Target = ContextInner;
 
@@ -80,6 +193,12 @@ impl Context {
 
         let applier_slab = slab.clone();
         let applier_stash = stash.clone();
+        let index_catalog = Arc::new(Mutex::new(CatalogCache::Unknown));
+        let applier_index_catalog = index_catalog.clone();
+        let compaction = Arc::new(Mutex::new(Compaction::new()));
+        let applier_compaction = compaction.clone();
+        let index_observers: Arc<Mutex<Vec<IndexObserver>>> = Arc::new(Mutex::new(Vec::new()));
+        let applier_index_observers = index_observers.clone();
 
         let span = span!(Level::TRACE, "Context Applier");
 
@@ -97,22 +216,78 @@ impl Context {
                 // than just this context
 
                 let _merged_head = applier_stash.apply_head(&applier_slab, &head).await.unwrap();
+                applier_compaction.lock().unwrap().record_write();
+                applier_index_catalog.lock().unwrap().reconsider();
+                notify_index_observers(&applier_index_observers, &head);
             }
         });
 
         let inner = ContextInner { slab,
                                    root_index_node: Arc::new(Mutex::new(None)),
+                                   root_index_resolved: Mutex::new(None),
                                    stash,
-                                   _applier: applier };
+                                   migrations: Mutex::new(HashMap::new()),
+                                   compaction,
+                                   index_catalog,
+                                   index_observers,
+                                   read_only: false,
+                                   _applier: Some(applier) };
 
         Context(Arc::new(inner))
     }
 
+    /// A read-only view of this context as it stands, for a series of reads which must all see the same consistent
+    /// cut. Heads which arrive later are applied to this context as usual, but not to the snapshot. Creating one only
+    /// copies the bookkeeping of the stash, which is cheap, as the heads within it are immutable.
+    ///
+    /// Entities retrieved via the snapshot may be read but not written: writes through it, or through any entity
+    /// retrieved from it, fail with [`WriteError::ReadOnly`].
+    pub fn snapshot(&self) -> Context {
+        let inner = ContextInner { slab:                self.slab.clone(),
+                                   root_index_node:     Arc::new(Mutex::new(self.root_index_node.lock().unwrap().clone())),
+                                   root_index_resolved: Mutex::new(*self.root_index_resolved.lock().unwrap()),
+                                   stash:               self.stash.snapshot(),
+                                   migrations:          Mutex::new(self.migrations.lock().unwrap().clone()),
+                                   compaction:          Arc::new(Mutex::new(Compaction::new())),
+                                   index_catalog:       Arc::new(Mutex::new(self.index_catalog.lock().unwrap().clone())),
+                                   index_observers:     Arc::new(Mutex::new(Vec::new())),
+                                   read_only:           true,
+                                   _applier:            None, };
+
+        Context(Arc::new(inner))
+    }
+
+    /// True for a [snapshot](Context::snapshot), which may not be written to
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Writes must check this before issuing any memos, lest they be sent to our peers and then fail
+    pub(crate) fn check_writable(&self) -> Result<(), WriteError> {
+        if self.read_only {
+            Err(WriteError::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Find an entity having the given value. Should a secondary index on the field have been declared, only the
+    /// index is consulted, just as it is by [`Context::query`], otherwise the root index is scanned.
     pub async fn try_fetch_kv(&self, key: &str, val: &str) -> Result<Option<Entity>, RetrieveError> {
-        // TODO implement field-specific indexes
-        // if I have an index for that field {
-        //    use it
-        //} else if I am allowed to scan this index...
+        if let Some(index) = IndexSecondary::open(self, key).await? {
+            for head in index.get(self, val).await? {
+                let mut entity = self.get_entity_from_head(head).await?;
+
+                // The index may list entities whose value has since changed, or which merely share a hash
+                if entity.get_value(key).await?.as_deref() == Some(val) {
+                    return Ok(Some(entity));
+                }
+            }
+
+            return Ok(None);
+        }
+
+        // TODO - consider whether we should be permitted to scan the whole root index
         let mut index = self.root_index().await?;
 
         match index.scan_first_kv(self, key, val).await? {
@@ -161,15 +336,268 @@ impl Context {
         }
     }
 
+    /// Declare a secondary index on the given field, such that try_fetch_kv and fetch_kv may locate entities by the
+    /// value of that field without scanning the root index. Entities which already have the field are indexed
+    /// immediately, and the index is maintained whenever the field is written thereafter. Entities written by contexts
+    /// which had yet to learn of the index are not listed until it is declared again, which indexes whatever is
+    /// missing.
+    pub async fn declare_index(&self, field: &str) -> Result<(), WriteError> {
+        self.check_writable()?;
+
+        let mut index = IndexSecondary::declare(self, field).await?;
+
+        for head in self.root_index().await?.entries(self).await? {
+            match head.entity_id() {
+                Some(EntityId { stype: EntityType::IndexNode, .. }) | Some(EntityId { stype: EntityType::Schema, .. }) => {
+                    continue
+                },
+                _ => {},
+            }
+
+            if let Some(value) = head.get_value_with(&self.slab, field, |_| {}).await? {
+                index.insert(self, &value, head).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Declare a composite index over several fields, such that queries with equality predicates on all of them may
+    /// locate entities without scanning the root index. If `covered` is non-empty the index also carries the values of
+    /// those fields, and queries which select nothing else are answered from the index alone. As with
+    /// [`declare_index`](Context::declare_index), existing entities are indexed immediately.
+    pub async fn declare_composite_index(&self, fields: &[&str], covered: &[&str]) -> Result<(), WriteError> {
+        self.check_writable()?;
+
+        let mut index = IndexComposite::declare(self, fields, covered).await?;
+
+        for head in self.root_index().await?.entries(self).await? {
+            match head.entity_id() {
+                Some(EntityId { stype: EntityType::IndexNode, .. }) | Some(EntityId { stype: EntityType::Schema, .. }) => {
+                    continue
+                },
+                _ => {},
+            }
+
+            let values = head.project_values(&self.slab).await?;
+            index.update(self, &HashMap::new(), &values, head).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Declare a full-text index on the given field, such that entities may be found by the words of that field via
+    /// [`search`](Context::search). As with [`declare_index`](Context::declare_index), existing entities are indexed
+    /// immediately. With `stemming`, words are reduced to a common stem so that "tigers" finds "tiger".
+    pub async fn declare_fulltext_index(&self, field: &str, stemming: bool) -> Result<(), WriteError> {
+        self.check_writable()?;
+
+        let mut index = IndexFullText::declare(self, field, stemming).await?;
+
+        for head in self.root_index().await?.entries(self).await? {
+            match head.entity_id() {
+                Some(EntityId { stype: EntityType::IndexNode, .. }) | Some(EntityId { stype: EntityType::Schema, .. }) => {
+                    continue
+                },
+                _ => {},
+            }
+
+            if let Some(value) = head.get_value_with(&self.slab, field, |_| {}).await? {
+                index.update(self, None, &value, head).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Declare an ordered index on the given field, such that queries comparing the field with a value (see
+    /// [`Query::compare`](crate::query::Query::compare)) draw their candidates from the matching range of the index,
+    /// rather than scanning the root index. As with [`declare_index`](Context::declare_index), existing entities are
+    /// indexed immediately. The index itself may be read in order via [`IndexOrdered::range`].
+    pub async fn declare_ordered_index(&self, field: &str) -> Result<(), WriteError> {
+        self.check_writable()?;
+
+        let mut index = IndexOrdered::declare(self, field).await?;
+
+        for head in self.root_index().await?.entries(self).await? {
+            match head.entity_id() {
+                Some(EntityId { stype: EntityType::IndexNode, .. }) | Some(EntityId { stype: EntityType::Schema, .. }) => {
+                    continue
+                },
+                _ => {},
+            }
+
+            if let Some(value) = head.get_value_with(&self.slab, field, |_| {}).await? {
+                index.update(self, None, &value, head).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the entities whose value for the given field contains any of the words in `terms`, most relevant first.
+    /// The field must have a full-text index, see [`declare_fulltext_index`](Context::declare_fulltext_index).
+    pub async fn search(&self, field: &str, terms: &str) -> Result<Vec<SearchHit>, RetrieveError> {
+        match IndexFullText::open(self, field).await? {
+            Some(index) => index.search(self, terms).await,
+            None => Err(RetrieveError::IndexNotInitialized),
+        }
+    }
+
+    /// Register (or replace) the Schema for a user-defined type. Subsequent writes to entities of that type via this
+    /// context, or any context which has received the schema entity, are validated against it
+    pub async fn register_schema(&self, schema: &Schema) -> Result<(), WriteError> {
+        self.check_writable()?;
+
+        let entity_id = schema.entity_id();
+
+        let parents = match self.root_index().await?.get(self, entity_id.id).await? {
+            Some(head) => head,
+            None => Head::Null,
+        };
+
+        let head = self.slab
+                       .new_memo(Some(entity_id),
+                                 parents,
+                                 MemoBody::FullyMaterialized { v: schema.to_values(),
+                                                               r: RelationSet::empty(),
+                                                               e: EdgeSet::empty(),
+                                                               t: EntityType::Schema, })
+                       .to_head();
+
+        self.update_indices(entity_id, &head).await?;
+        self.type_index(schema.type_id).await?;
+
+        Ok(())
+    }
+
+    /// Retrieve the Schema for a user-defined type, if one has been registered
+    pub async fn get_schema(&self, type_id: TypeId) -> Result<Option<Schema>, RetrieveError> {
+        match self.root_index().await?.get(self, EntityId::schema(type_id).id).await? {
+            Some(head) => Ok(Schema::from_values(&head.project_values(&self.slab).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) async fn require_schema(&self, type_id: TypeId) -> Result<Schema, WriteError> {
+        self.get_schema(type_id)
+            .await?
+            .ok_or(WriteError::SchemaViolation(SchemaViolation::SchemaNotFound(type_id)))
+    }
+
+    /// Register a Migration for a user-defined type. Memos written under older schema versions are projected through
+    /// the registered migrations whenever they are read via this context
+    pub fn register_migration(&self, type_id: TypeId, migration: Migration) {
+        self.migrations
+            .lock()
+            .unwrap()
+            .entry(type_id)
+            .or_default()
+            .push(migration);
+    }
+
+    fn migrations_for(&self, type_id: TypeId) -> Vec<Migration> {
+        self.migrations.lock().unwrap().get(&type_id).cloned().unwrap_or_default()
+    }
+
+    /// Retrieve a single value from the head of a user-defined type, migrating older memos to the current schema
+    pub(crate) async fn get_typed_value(&self, head: &Head, schema: &Schema, key: &str) -> Result<Option<String>, RetrieveError> {
+        let migrations = self.migrations_for(schema.type_id);
+
+        head.get_value_with(&self.slab, key, |values| {
+                migration::migrate_values(&migrations, values, schema.version)
+            })
+            .await
+    }
+
+    pub(crate) async fn get_typed_values(&self, head: &Head, schema: &Schema) -> Result<HashMap<String, String>, RetrieveError> {
+        let migrations = self.migrations_for(schema.type_id);
+
+        head.project_values_with(&self.slab, |values| {
+                migration::migrate_values(&migrations, values, schema.version)
+            })
+            .await
+    }
+
+    /// Rewrite every entity of the given type which has memos written under an older schema version as a keyframe in
+    /// the current shape, such that subsequent reads need not migrate them. Returns the number of entities rewritten.
+    pub async fn write_keyframes(&self, type_id: TypeId) -> Result<usize, WriteError> {
+        self.check_writable()?;
+
+        let schema = self.require_schema(type_id).await?;
+        let migrations = self.migrations_for(type_id);
+
+        let mut rewritten = 0;
+
+        for head in self.root_index().await?.entries(self).await? {
+            let entity_id = match head.entity_id() {
+                Some(entity_id) if entity_id.stype == EntityType::Custom(type_id) => entity_id,
+                _ => continue,
+            };
+
+            let stale = AtomicBool::new(false);
+            let mut values = head.project_values_with(&self.slab, |values| {
+                                     let version = values.get(VERSION_KEY).and_then(|v| v.parse().ok()).unwrap_or(1);
+                                     if version < schema.version {
+                                         stale.store(true, Ordering::Relaxed);
+                                     }
+                                     migration::migrate_values(&migrations, values, schema.version)
+                                 })
+                                 .await?;
+
+            if !stale.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            values.insert(VERSION_KEY.to_string(), schema.version.to_string());
+
+            let mut edges = EdgeSet::empty();
+            for edgelink in head.project_occupied_edges(&self.slab).await? {
+                if let EdgeLink::Occupied { slot_id, head } = edgelink {
+                    edges.insert(slot_id, head);
+                }
+            }
+
+            let relations = head.project_relations(&self.slab).await?;
+
+            let keyframe = self.slab
+                               .new_memo(Some(entity_id),
+                                         head,
+                                         MemoBody::FullyMaterialized { v: values,
+                                                                       r: relations,
+                                                                       e: edges,
+                                                                       t: entity_id.stype, })
+                               .to_head();
+
+            self.update_indices(entity_id, &keyframe).await?;
+            rewritten += 1;
+        }
+
+        Ok(rewritten)
+    }
+
+    /// Periodically call write_keyframes for the given type in the background until the returned handle is dropped.
+    /// Note that the job holds a reference to this context for as long as it runs.
+    pub fn spawn_keyframe_job(&self, type_id: TypeId, interval: Duration) -> RemoteHandle<()> {
+        let context = self.clone();
+
+        crate::util::task::spawn_with_handle(async move {
+            loop {
+                if let Err(e) = context.write_keyframes(type_id).await {
+                    warn!("keyframe job for type {} failed: {:?}", type_id, e);
+                }
+
+                Delay::new(interval).await;
+            }
+        })
+    }
+
     pub fn concise_contents(&self) -> String {
         self.stash.concise_contents()
     }
 
     // Magically transport entity heads into another context in the same process.
-    // This is a temporary hack for testing purposes until such time as proper context exchange is enabled
-    // QUESTION: should context exchanges be happening constantly, but often ignored? or requested? Probably the former,
-    //           sent based on an interval and/or compaction ( which would also likely be based on an interval and/or
-    // present context size)
+    // This is a temporary hack for testing purposes, which sidesteps the network. See send_context for the real thing
     #[tracing::instrument]
     pub async fn hack_send_context(&self, other: &Context) -> Result<usize, WriteError> {
         self.compact().await?;
@@ -183,11 +611,35 @@ impl Context {
 
             let apply_head = other.slab.agent.localize_head(&head, &from_slabref, false);
             other.apply_head(&apply_head).await?;
+            other.recheck_index_catalog();
         }
 
         Ok(memoref_count)
     }
 
+    /// Send the heads of the index nodes in our stash to every peer slab which has contexts of its own, after
+    /// compacting it. Those contexts apply the heads just as they would any index node memo they receive, so that
+    /// their reads are causally consistent with the writes made in this context, whichever machine they are on. The
+    /// exchange is not stored by either slab. Returns the number of memorefs sent. See also
+    /// [`CompactionPolicy::exchange`] to do so after every background compaction.
+    pub async fn send_context(&self) -> Result<usize, WriteError> {
+        self.compact().await?;
+
+        Ok(self.send_stash())
+    }
+
+    fn send_stash(&self) -> usize {
+        let heads: Vec<Head> = self.stash.iter().collect();
+        let memoref_count = heads.iter().map(|head| head.len()).sum();
+
+        for chunk in heads.chunks(MAX_EXCHANGE_HEADS) {
+            let memoref = self.slab.agent.new_transient_memo(MemoBody::ContextExchange(chunk.to_vec()));
+            self.slab.agent.send_to_exchange_subscribers(&memoref);
+        }
+
+        memoref_count
+    }
+
     pub fn try_root_index_node(&self) -> Result<Head, RetrieveError> {
         {
             if let Some(ref node) = *self.root_index_node.lock().unwrap() {
@@ -216,14 +668,99 @@ impl Context {
             }
 
             if let Ok(node) = self.try_root_index_node() {
-                let index = IndexFixed::new_from_head(5, node);
-                return Ok(index);
+                let (node, shape) = self.resolve_root_index(node).await?;
+                return Ok(IndexFixed::new_from_head_with_shape(node, shape));
             }
 
             Delay::new(Duration::from_millis(50)).await;
         }
     }
 
+    /// Follow the root index seed to the current root index node, and read its shape. This requires projecting the
+    /// values of the root, so the outcome is retained for the lifetime of the context.
+    async fn resolve_root_index(&self, seed: Head) -> Result<(Head, IndexShape), RetrieveError> {
+        let resolved = *self.root_index_resolved.lock().unwrap();
+
+        let mut node = seed;
+        if let Some((successors, shape)) = resolved {
+            for _ in 0..successors {
+                node = self.root_index_successor(node).await?;
+            }
+            return Ok((node, shape));
+        }
+
+        let mut successors = 0;
+        loop {
+            self.mut_update_index_head_for_consistency(&mut node).await?;
+            let values = node.project_values(&self.slab).await?;
+
+            // A migrated root index points to its successor
+            if !values.contains_key(ROOT_INDEX_SUCCESSOR_KEY) {
+                // Systems created before the shape was recorded have the default shape
+                let shape = IndexShape::from_values(&values).unwrap_or_else(IndexShape::for_key::<u64>);
+                shape.check::<u64>().map_err(|_| RetrieveError::Malformed)?;
+
+                *self.root_index_resolved.lock().unwrap() = Some((successors, shape));
+                return Ok((node, shape));
+            }
+
+            node = self.root_index_successor(node).await?;
+            successors += 1;
+        }
+    }
+
+    async fn root_index_successor(&self, mut node: Head) -> Result<Head, RetrieveError> {
+        self.mut_update_index_head_for_consistency(&mut node).await?;
+        node.get_edge(&self.slab, 0).await?.ok_or(RetrieveError::IndexNotInitialized)
+    }
+
+    /// Migrate the root index to a new fan-out and depth. All entries are copied into a new root index of the given
+    /// shape, and the existing root is then superseded by a keyframe which points to its successor, such that other
+    /// contexts find the new root by way of the same root index seed.
+    ///
+    /// Writes to the root index should be quiesced while the migration is underway, as entries written concurrently
+    /// to the old root may not be carried over. Contexts which had already resolved the root index prior to the
+    /// migration will continue to use the old one, and should be recreated. As the root index is keyed by entity id,
+    /// shapes which do not cover a u64 are rejected.
+    pub async fn reindex(&self, shape: IndexShape) -> Result<(), WriteError> {
+        self.check_writable()?;
+        shape.check::<u64>()?;
+
+        let old = self.root_index().await?;
+        let successors = self.root_index_resolved.lock().unwrap().map_or(0, |(successors, _)| successors);
+        let mut new = IndexFixed::new_with_shape(self, shape)?;
+
+        for head in old.entries(self).await? {
+            if let Some(entity_id) = head.entity_id() {
+                new.insert(self, entity_id.id, head).await?;
+            }
+        }
+
+        let mut root = old.root_head().clone();
+        self.mut_update_index_head_for_consistency(&mut root).await?;
+
+        let mut values = HashMap::new();
+        values.insert(ROOT_INDEX_SUCCESSOR_KEY.to_string(), new.get_root_entity_id().to_string());
+
+        let mut edges = EdgeSet::empty();
+        edges.insert(0, new.root_head().clone());
+
+        let root_id = old.get_root_entity_id();
+        let keyframe = self.slab
+                           .new_memo(Some(root_id),
+                                     root,
+                                     MemoBody::FullyMaterialized { v: values,
+                                                                   r: RelationSet::empty(),
+                                                                   e: edges,
+                                                                   t: EntityType::IndexNode, })
+                           .to_head();
+        self.apply_head(&keyframe).await?;
+
+        *self.root_index_resolved.lock().unwrap() = Some((successors + 1, shape));
+
+        Ok(())
+    }
+
     pub fn get_resident_entity_head(&self, entity_id: EntityId) -> Head {
         self.stash.get_head(entity_id).clone()
     }
@@ -264,19 +801,18 @@ impl Context {
     }
 
     /// Attempt to compress the present query context.
-    /// We do this by issuing Relation memos for any entity heads which reference other entity heads presently in the
-    /// query context. Then we can remove the now-referenced entity heads, and repeat the process in a topological
-    /// fashion, confident that these referenced entity heads will necessarily be included in subsequent projection
-    /// as a result.
-    pub async fn compact(&self) -> Result<(), WriteError> {
-        //        let before = self.stash.concise_contents();
-
-        // TODO: implement topological Head iterator for stash
-        //      right now we're just doing a linear pass, which will
-        //      yield sub-optimal compaction
-
-        // iterate all heads in the stash
-        for parent_head in self.stash.iter() {
+    /// We do this by issuing Edge memos for any entity heads which reference other entity heads presently in the
+    /// query context. Then we can remove the now-referenced entity heads, confident that they will necessarily be
+    /// included in subsequent projection as a result. Heads are visited in topological order, children before their
+    /// parents, so the edges issued for each parent lead to heads which have already absorbed those of their own
+    /// children, and a single pass collapses an index path of any depth onto its root.
+    pub async fn compact(&self) -> Result<CompactionStats, WriteError> {
+        self.check_writable()?;
+
+        let (heads_before, memorefs_before) = self.stash.size();
+        let mut memos_issued = 0;
+
+        for parent_head in self.stash.iter_topological() {
             // TODO POSTMERGE - ideally we'd have a better signal for when we have
             //                  reached the end of a given concurrent set of memos
             //                  versus doing a descends test
@@ -308,11 +844,21 @@ impl Context {
                 let head = self.slab.new_memo(Some(entity_id), parent_head, memobody.clone()).to_head();
 
                 self.apply_head(&head).await?;
+                memos_issued += 1;
             }
         }
 
-        //        debug!("COMPACT Before: {:?}, After: {:?}", before, self.stash.concise_contents() );
-        Ok(())
+        let (heads_after, memorefs_after) = self.stash.size();
+        let stats = CompactionStats { heads_before,
+                                      heads_after,
+                                      memorefs_before,
+                                      memorefs_after,
+                                      memos_issued };
+
+        debug!("COMPACT {:?}, contents: {}", stats, self.stash.concise_contents());
+        self.compaction.lock().unwrap().record(stats);
+
+        Ok(stats)
     }
 
     pub async fn is_fully_materialized(&self) -> Result<bool, RetrieveError> {
@@ -325,15 +871,191 @@ impl Context {
     }
 
     pub(crate) async fn update_indices(&self, entity_id: EntityId, head: &Head) -> Result<(), WriteError> {
-        self.root_index().await?.insert(self, entity_id.id, head.clone()).await
-        // TODO - update
+        self.check_writable()?;
+
+        self.root_index().await?.insert(self, entity_id.id, head.clone()).await?;
+
+        // Entities of user-defined types are also listed by type
+        if let EntityType::Custom(type_id) = entity_id.stype {
+            self.type_index(type_id).await?.insert(self, head.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Open the index of the entities of a user-defined type, creating it if need be. Any entities of the type which
+    /// were written before the index was created are indexed when it is.
+    async fn type_index(&self, type_id: TypeId) -> Result<IndexTyped, WriteError> {
+        let (mut index, created) = IndexTyped::declare(self, type_id).await?;
+
+        if created {
+            for head in self.root_index().await?.entries(self).await? {
+                if head.entity_id().map(|entity_id| entity_id.stype) == Some(EntityType::Custom(type_id)) {
+                    index.insert(self, head).await?;
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Retrieve every entity of the given type. Entities of user-defined types are listed by a per-type index, which
+    /// is maintained as they are written, so the root index need not be scanned for them. Should the index for the
+    /// type not be found, the root index is scanned after all.
+    pub async fn entities_of_type(&self, stype: EntityType) -> Result<Vec<Entity>, RetrieveError> {
+        let typed = match stype {
+            EntityType::Custom(type_id) => IndexTyped::open(self, type_id).await?,
+            _ => None,
+        };
+
+        let heads = match typed {
+            Some(index) => index.entries(self).await?,
+            None => self.root_index().await?.entries(self).await?,
+        };
+
+        let mut entities = Vec::new();
+        for head in heads {
+            if head.entity_id().map(|entity_id| entity_id.stype) == Some(stype) {
+                entities.push(self.get_entity_from_head(head).await?);
+            }
+        }
+
+        Ok(entities)
+    }
+
+    /// The current head of the index catalog, and the indexes it lists, if any index has been declared. The catalog is
+    /// read again only when its head has changed, so that writes need not look up every index which might concern them.
+    pub(crate) async fn index_catalog(&self) -> Result<Option<(Head, Arc<Vec<Declared>>)>, RetrieveError> {
+        let catalog_id = EntityId::index_catalog();
+
+        let cached = self.index_catalog.lock().unwrap().clone();
+        let mut head = match cached {
+            CatalogCache::Read { ref head, .. } => head.clone(),
+            CatalogCache::Absent => return Ok(None),
+            CatalogCache::Unknown => {
+                match self.root_index().await?.get(self, catalog_id.id).await? {
+                    Some(head) if head.entity_id() == Some(catalog_id) => head,
+                    _ => {
+                        *self.index_catalog.lock().unwrap() = CatalogCache::Absent;
+                        return Ok(None);
+                    },
+                }
+            },
+        };
+        self.mut_update_index_head_for_consistency(&mut head).await?;
+
+        if let CatalogCache::Read { head: ref cached_head, ref declared } = cached {
+            if *cached_head == head {
+                return Ok(Some((head, declared.clone())));
+            }
+        }
+
+        let declared = Arc::new(read_catalog(self, &head).await?);
+        *self.index_catalog.lock().unwrap() = CatalogCache::Read { head:     head.clone(),
+                                                                   declared: declared.clone(), };
+
+        Ok(Some((head, declared)))
+    }
+
+    /// Look for the index catalog once more, should it not have been found before, as heads received from elsewhere or
+    /// a newly declared index may lead to it
+    pub(crate) fn recheck_index_catalog(&self) {
+        self.index_catalog.lock().unwrap().reconsider();
+    }
+
+    /// The secondary, full-text and composite indexes which have been declared
+    pub(crate) async fn declared_indexes(&self) -> Result<Arc<Vec<Declared>>, RetrieveError> {
+        Ok(self.index_catalog().await?.map(|(_, declared)| declared).unwrap_or_default())
+    }
+
+    /// Update the secondary, full-text and composite indexes for any of the given values of an entity which are
+    /// indexed. The previous head of the entity is consulted so that superseded values may be removed from the index
+    pub(crate) async fn update_secondary_indices(&self, previous: &Head, head: &Head, values: &HashMap<String, String>)
+                                                 -> Result<(), WriteError> {
+        let entity_id = head.entity_id().ok_or(WriteError::BadTarget)?;
+        let declared = self.declared_indexes().await?;
+
+        for (field, value) in values.iter() {
+            let secondary = declared.iter().any(|index| index.is_on(IndexKind::Secondary, field));
+            let fulltext = declared.iter().any(|index| index.is_on(IndexKind::FullText, field));
+            let ordered = declared.iter().any(|index| index.is_on(IndexKind::Ordered, field));
+            if !secondary && !fulltext && !ordered {
+                continue;
+            }
+
+            let old = if previous.is_some() {
+                previous.get_value_with(&self.slab, field, |_| {}).await?
+            } else {
+                None
+            };
+            if old.as_ref() == Some(value) {
+                continue;
+            }
+
+            if secondary {
+                if let Some(mut index) = IndexSecondary::open(self, field).await? {
+                    if let Some(ref old) = old {
+                        index.remove(self, old, entity_id).await?;
+                    }
+                    index.insert(self, value, head.clone()).await?;
+                }
+            }
+
+            if fulltext {
+                if let Some(mut index) = IndexFullText::open(self, field).await? {
+                    index.update(self, old.as_deref(), value, head.clone()).await?;
+                }
+            }
+
+            if ordered {
+                if let Some(mut index) = IndexOrdered::open(self, field).await? {
+                    index.update(self, old.as_deref(), value, head.clone()).await?;
+                }
+            }
+        }
+
+        let composites: Vec<&Declared> =
+            declared.iter()
+                    .filter(|index| index.kind == IndexKind::Composite)
+                    .filter(|index| values.keys().any(|field| index.is_affected_by(field)))
+                    .collect();
+
+        if !composites.is_empty() {
+            let old = if previous.is_some() { previous.project_values(&self.slab).await? } else { HashMap::new() };
+            let new = head.project_values(&self.slab).await?;
+
+            for declared in composites {
+                let fields: Vec<&str> = declared.fields.iter().map(|f| f.as_str()).collect();
+                if let Some(mut index) = IndexComposite::open(self, &fields).await? {
+                    index.update(self, &old, &new, head.clone()).await?;
+                }
+            }
+        }
+
+        Ok(())
     }
 
     /// Called by the Slab whenever memos matching one of our subscriptions comes in, or by the Entity when an edit is
     /// made
     pub(crate) async fn apply_head(&self, head: &Head) -> Result<Head, WriteError> {
         // println!("Context.apply_entity_head({}, {:?}) ", entity_id, head.memo_ids() );
-        self.stash.apply_head(&self.slab, head).await
+        self.check_writable()?;
+        self.compaction.lock().unwrap().record_write();
+        let merged = self.stash.apply_head(&self.slab, head).await?;
+        notify_index_observers(&self.index_observers, head);
+
+        Ok(merged)
+    }
+
+    /// Be sent each index head once it has been applied to this context, whether written here or received from
+    /// elsewhere. Heads are dropped should the channel be full, in which case the returned flag is raised, and the
+    /// observer must assume that anything may have changed. A snapshot never changes, so its observers hear nothing.
+    pub(crate) fn observe_applied(&self, tx: mpsc::Sender<Head>) -> Arc<AtomicBool> {
+        let lagged = Arc::new(AtomicBool::new(false));
+        self.index_observers.lock().unwrap().push(IndexObserver { tx,
+                                                                  lagged: lagged.clone() });
+
+        lagged
     }
 
     pub async fn get_entity(&self, entity_id: EntityId) -> Result<Option<Entity>, RetrieveError> {
@@ -351,6 +1073,27 @@ impl Context {
         }
     }
 
+    /// Retrieve several entities at once, walking the root index only once for all of them. The outcome for each
+    /// entity id is in the same position as the id.
+    pub async fn get_entities(&self, entity_ids: &[EntityId]) -> Result<Vec<Option<Entity>>, RetrieveError> {
+        let keys: Vec<u64> = entity_ids.iter().map(|entity_id| entity_id.id).collect();
+
+        let mut entities = Vec::with_capacity(keys.len());
+        for head in self.root_index().await?.get_many(self, &keys).await? {
+            entities.push(match head {
+                              Some(head) => {
+                                  Some(Entity { id: head.entity_id()
+                                                    .ok_or(RetrieveError::InvalidHead(InvalidHead::MissingEntityId))?,
+                                                head,
+                                                context: self.clone() })
+                              },
+                              None => None,
+                          });
+        }
+
+        Ok(entities)
+    }
+
     /// Update a given Head with any relevant information to ensure that our consistency model invariants are met
     #[tracing::instrument(level = "info")]
     pub(crate) async fn mut_update_index_head_for_consistency(&self, mut_head: &mut Head) -> Result<bool, RetrieveError> {
@@ -383,8 +1126,7 @@ impl Context {
         //        }
 
         let apply_head = match mut_head.entity_id() {
-            Some(entity_id @ EntityId { stype: EntityType::Record,
-                            .. }) => {
+            Some(entity_id) if entity_id.stype != EntityType::IndexNode => {
                 // TODO: figure out a way to noop here in the case that the EntityHead in question
                 //       was pulled against a sufficiently identical context stash state.
                 //       Perhaps stash edit increment? how can we get this to be really granular?
@@ -394,7 +1136,7 @@ impl Context {
                     None => return Ok(false),
                 }
             },
-            _ => panic!("Can only be called for EntityType::Record heads"),
+            _ => panic!("Can only be called for non-EntityType::IndexNode heads"),
         };
 
         let applied = mut_head.mut_apply(&apply_head, &self.slab).await?;
@@ -432,6 +1174,7 @@ mod test {
             EntityId,
             MemoBody,
         },
+        Entity,
         Network,
         Slab,
     };
@@ -543,6 +1286,37 @@ mod test {
         assert_eq!(context.stash.concise_contents(), "I4>I3", "Valid contents");
     }
 
+    #[unbase_test_util::async_test]
+    async fn context_topological_compaction() {
+        let net = Network::create_new_system();
+        let slab = Slab::new(&net);
+        let context = slab.create_context();
+
+        let mut entity_ids = Vec::new();
+        for i in 0..20 {
+            let entity = Entity::new_with_single_kv(&context, "number", &i.to_string()).await.unwrap();
+            entity_ids.push(entity.id);
+        }
+
+        // Every tier of the root index has edits which its parent's edge does not yet reflect
+        let stats = context.compact().await.unwrap();
+        assert_eq!(stats.heads_before, 8);
+        assert_eq!(stats.memos_issued, 7);
+
+        // A single pass collapses the whole path onto the root index seed, which is all that remains
+        assert_eq!(stats.heads_after, 1);
+        assert_eq!(context.stash.concise_contents(), "I9001>I15082743300840488589");
+
+        for entity_id in entity_ids {
+            assert!(context.get_entity(entity_id).await.unwrap().is_some());
+        }
+
+        // Nothing more to be done
+        let stats = context.compact().await.unwrap();
+        assert_eq!(stats.memos_issued, 0);
+        assert_eq!(stats.heads_before, stats.heads_after);
+    }
+
     // TODO POSTMERGE - restore these tests
 
     // #[unbase_test_util::async_test]
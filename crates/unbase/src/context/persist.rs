@@ -0,0 +1,131 @@
+use crate::{
+    context::Context,
+    error::RetrieveError,
+    head::{
+        serde::HeadSeed,
+        Head,
+    },
+    slab::{
+        slabref_serde::SlabRefSeed,
+        EntityType,
+        MemoRef,
+        SlabHandle,
+        SlabRef,
+        PREFETCH_TIMEOUT,
+    },
+    util::serde::*,
+};
+
+use std::fmt;
+use tracing::debug;
+
+const SAVE_VERSION: u8 = 1;
+
+/// The saved form of a context: the slab which saved it, from which the memos may be requested, and its stash heads
+struct SavedContext<'a> {
+    slabref: &'a SlabRef,
+    heads:   Vec<Head>,
+}
+
+impl<'a> StatefulSerialize for SavedContext<'a> {
+    fn serialize<S>(&self, serializer: S, helper: &SerializeHelper) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut seq = serializer.serialize_seq(Some(3))?;
+        seq.serialize_element(&SAVE_VERSION)?;
+        seq.serialize_element(&SerializeWrapper(self.slabref, helper))?;
+        seq.serialize_element(&SerializeWrapper(&self.heads, helper))?;
+        seq.end()
+    }
+}
+
+struct SavedContextSeed<'a> {
+    dest_slab: &'a SlabHandle,
+}
+
+impl<'a> DeserializeSeed for SavedContextSeed<'a> {
+    type Value = Vec<Head>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a> Visitor for SavedContextSeed<'a> {
+    type Value = Vec<Head>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("saved Context")
+    }
+
+    fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+        where V: SeqVisitor
+    {
+        match visitor.visit::<u8>()? {
+            Some(SAVE_VERSION) => {},
+            Some(version) => return Err(DeError::custom(format!("unsupported saved Context version {}", version))),
+            None => return Err(DeError::invalid_length(0, &self)),
+        }
+
+        let origin_slabref: SlabRef = match visitor.visit_seed(SlabRefSeed { dest_slab: self.dest_slab })? {
+            Some(value) => value,
+            None => return Err(DeError::invalid_length(1, &self)),
+        };
+
+        let seed = HeadSeed { dest_slab:      self.dest_slab,
+                              origin_slabref: &origin_slabref, };
+
+        match visitor.visit_seed(VecSeed(seed))? {
+            Some(heads) => Ok(heads),
+            None => Err(DeError::invalid_length(2, &self)),
+        }
+    }
+}
+
+impl Context {
+    /// Serialize the stash, such that a context restored from it with [`Context::restore`] - after a restart, say -
+    /// sees no less than this one has. Only memo references are saved, not the memos themselves, so these must remain
+    /// available from this slab or its peers. The stash is smallest just after [`Context::compact`].
+    pub fn save(&self) -> Vec<u8> {
+        let saved = SavedContext { slabref: &self.slab.my_ref,
+                                   heads:   self.stash.iter().collect(), };
+
+        let return_address = self.slab.my_ref.get_return_address();
+        let helper = SerializeHelper { dest_slab_id:   &self.slab.my_ref.slab_id,
+                                       return_address: &return_address, };
+
+        serde_json::to_vec(&SerializeWrapper(&saved, &helper)).expect("serde_json::to_vec")
+    }
+
+    /// Create a context on the given slab from one which was saved with [`Context::save`], possibly by another slab.
+    /// The saved heads are applied to the new stash, retrieving memos from the slabs which hold them as required, so
+    /// reads are consistent with everything the saved context had seen by the time this returns.
+    pub async fn restore(slab: &SlabHandle, bytes: &[u8]) -> Result<Context, RetrieveError> {
+        let heads = {
+            let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+            SavedContextSeed { dest_slab: slab }.deserialize(&mut deserializer)
+                                                .map_err(|e| {
+                                                    debug!("Context::restore failed to read saved context: {}", e);
+                                                    RetrieveError::Malformed
+                                                })?
+        };
+
+        if heads.iter().any(|head| head.entity_id().map(|entity_id| entity_id.stype) != Some(EntityType::IndexNode)) {
+            return Err(RetrieveError::Malformed);
+        }
+
+        // Fetch the head memos all at once, rather than one by one as the stash happens to need them. Any which don't
+        // arrive in time are retrieved as they are applied
+        let memorefs: Vec<MemoRef> = heads.iter().flat_map(|head| head.iter().cloned()).collect();
+        slab.fetch_memos(&memorefs, PREFETCH_TIMEOUT).await;
+
+        let context = Context::new(slab.clone());
+        for head in heads.iter() {
+            context.apply_head(head).await?;
+        }
+
+        Ok(context)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    context::Context,
+    entity::Entity,
+    error::RetrieveError,
+    slab::{
+        EntityId,
+        EntityType,
+    },
+};
+
+use std::fmt;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// The position of a [`Scan`], such that it may be resumed later - possibly by another process, against another
+/// context. Tokens may be serialized, or rendered as a string with `to_string` and read back with `parse`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanToken {
+    /// The key of the last root index entry which was visited, if any
+    after: Option<u64>,
+}
+
+/// A resumable scan over every Record and Custom entity in a context, in the order of the root index.
+///
+/// Each page is read afresh from the root index, so the scan holds nothing open between pages. Entities which are
+/// added behind the position of the scan while it is underway will not be visited.
+pub struct Scan {
+    context:   Context,
+    after:     Option<u64>,
+    page_size: usize,
+    finished:  bool,
+}
+
+impl Scan {
+    pub(crate) fn new(context: Context) -> Self {
+        Scan { context,
+               after: None,
+               page_size: DEFAULT_PAGE_SIZE,
+               finished: false }
+    }
+
+    /// Set the maximum number of entities returned by each call to `next_page`
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        assert!(page_size > 0, "page size must be at least one");
+        self.page_size = page_size;
+        self
+    }
+
+    /// Continue from the position recorded in the given token
+    pub fn resume(mut self, token: ScanToken) -> Self {
+        self.after = token.after;
+        self.finished = false;
+        self
+    }
+
+    /// The current position of the scan
+    pub fn token(&self) -> ScanToken {
+        ScanToken { after: self.after }
+    }
+
+    /// True once a page has reached the end of the root index
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Retrieve the next page of entities. An empty page means the scan is finished.
+    pub async fn next_page(&mut self) -> Result<Vec<Entity>, RetrieveError> {
+        let mut page = Vec::new();
+        if self.finished {
+            return Ok(page);
+        }
+
+        let root_index = self.context.root_index().await?;
+
+        while page.len() < self.page_size {
+            // Only ask for as many entries as we need, so that none are passed over by the token
+            let wanted = self.page_size - page.len();
+            let entries = root_index.entries_after(&self.context, self.after, wanted).await?;
+            let exhausted = entries.len() < wanted;
+
+            for head in entries {
+                let entity_id = match head.entity_id() {
+                    Some(entity_id) => entity_id,
+                    None => continue,
+                };
+                self.after = Some(entity_id.id);
+
+                match entity_id {
+                    EntityId { stype: EntityType::Record, .. } | EntityId { stype: EntityType::Custom(_), .. } => {
+                        page.push(self.context.get_entity_from_head(head).await?);
+                    },
+                    _ => {},
+                }
+            }
+
+            if exhausted {
+                self.finished = true;
+                break;
+            }
+        }
+
+        Ok(page)
+    }
+}
+
+impl fmt::Debug for Scan {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Scan")
+           .field("after", &self.after)
+           .field("page_size", &self.page_size)
+           .field("finished", &self.finished)
+           .finish()
+    }
+}
+
+impl fmt::Display for ScanToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.after {
+            Some(after) => write!(f, "{:016x}", after),
+            None => write!(f, "start"),
+        }
+    }
+}
+
+impl std::str::FromStr for ScanToken {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "start" => Ok(ScanToken { after: None }),
+            _ if s.len() == 16 => {
+                u64::from_str_radix(s, 16).map(|after| ScanToken { after: Some(after) })
+                                          .map_err(|_| ())
+            },
+            _ => Err(()),
+        }
+    }
+}
+
+impl Context {
+    /// Begin a paged scan over every Record and Custom entity in this context
+    pub fn scan(&self) -> Scan {
+        Scan::new(self.clone())
+    }
+}
@@ -1,6 +1,5 @@
 //#![allow(dead_code)]
 use std::{
-    iter,
     mem,
     sync::{
         Arc,
@@ -17,7 +16,6 @@ use crate::{
         EntityType,
         SlabHandle,
         SlotId,
-        MAX_SLOTS,
     },
 };
 /// # What is a Stash?
@@ -94,7 +92,7 @@ use crate::{
 pub struct Stash {
     inner: Arc<Mutex<StashInner>>,
 }
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub(super) struct StashInner {
     items:     Vec<Option<StashItem>>,
     index:     Vec<(EntityId, ItemId)>,
@@ -107,6 +105,12 @@ impl Stash {
         Default::default()
     }
 
+    /// A separate copy of the present contents of the `Stash`, which is unaffected by later edits to this one. Only
+    /// the bookkeeping is copied, as the heads themselves are immutable.
+    pub fn snapshot(&self) -> Stash {
+        Stash { inner: Arc::new(Mutex::new(self.inner.lock().unwrap().clone())) }
+    }
+
     /// Returns the number of entities in the `Stash` including placeholders.
     pub fn _count(&self) -> usize {
         self.inner.lock().unwrap().index.len()
@@ -195,6 +199,24 @@ impl Stash {
         StashIterator::new(&self.inner)
     }
 
+    /// Returns an iterator for all Heads presently in the stash, in topological order: each entity is visited after
+    /// every entity which its edges lead to, so leaves come first and the root comes last. The order is fixed when the
+    /// iterator is created, but each Head is as of the moment it is visited.
+    pub(crate) fn iter_topological(&self) -> TopologicalStashIterator {
+        TopologicalStashIterator::new(&self.inner)
+    }
+
+    /// Returns the number of entity heads in the `Stash`, and the number of memorefs across all of them
+    pub fn size(&self) -> (usize, usize) {
+        let inner = self.inner.lock().unwrap();
+
+        inner.items
+             .iter()
+             .flatten()
+             .filter(|item| item.head.is_some())
+             .fold((0, 0), |(heads, memorefs), item| (heads + 1, memorefs + item.head.len()))
+    }
+
     /// Get Head (if resident) for the provided entity_id
     pub fn get_head(&self, entity_id: EntityId) -> Head {
         let inner = self.inner.lock().unwrap();
@@ -339,6 +361,42 @@ impl Stash {
 }
 
 impl StashInner {
+    /// The entity ids of all items, including placeholders, such that each follows every item which it relates to
+    fn topological_order(&self) -> Vec<EntityId> {
+        let mut order = Vec::with_capacity(self.index.len());
+        let mut visited = vec![false; self.items.len()];
+
+        for &(_, item_id) in self.index.iter() {
+            // Depth first, emitting each item once all of its relations have been
+            let mut stack = vec![(item_id, false)];
+
+            while let Some((item_id, expanded)) = stack.pop() {
+                let item = match self.items[item_id] {
+                    Some(ref item) => item,
+                    None => continue,
+                };
+
+                if expanded {
+                    order.push(item.entity_id);
+                    continue;
+                }
+                if visited[item_id] {
+                    continue;
+                }
+                visited[item_id] = true;
+
+                stack.push((item_id, true));
+                for rel_item_id in item.relations.iter().rev().flatten() {
+                    if !visited[*rel_item_id] {
+                        stack.push((*rel_item_id, false));
+                    }
+                }
+            }
+        }
+
+        order
+    }
+
     /// Fetch item id for a entity if present
     pub fn get_item_id_for_entity(&self, entity_id: EntityId) -> Option<ItemId> {
         match self.index.binary_search_by(|x| x.0.cmp(&entity_id)) {
@@ -374,7 +432,7 @@ impl StashInner {
             let item = self.items[item_id].as_mut().unwrap();
 
             // we have an existing relation in this slot
-            if let Some(ex_rel_item_id) = item.relations[slot_id as usize] {
+            if let Some(ex_rel_item_id) = item.relations.get(slot_id as usize).cloned().flatten() {
                 // If its the same as we're setting it to, then bail out
                 if Some(ex_rel_item_id) == maybe_rel_item_id {
                     return;
@@ -394,8 +452,14 @@ impl StashInner {
             self.increment_item(rel_item_id);
         }
 
-        // Set the actual relation item id
+        // Set the actual relation item id, growing the relations only as far as the highest occupied slot
         let item = self.items[item_id].as_mut().unwrap();
+        if slot_id as usize >= item.relations.len() {
+            if maybe_rel_item_id.is_none() {
+                return;
+            }
+            item.relations.resize(slot_id as usize + 1, None);
+        }
         item.relations[slot_id as usize] = maybe_rel_item_id;
     }
 
@@ -429,7 +493,7 @@ impl StashInner {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct StashItem {
     entity_id:    EntityId,
     head:         Head,
@@ -442,9 +506,8 @@ impl StashItem {
     fn new(entity_id: EntityId, head: Head) -> Self {
         StashItem { entity_id,
                     head,
-                    // QUESTION: should we preallocate all possible relation slots? or manage the length of relations
-                    // vec?
-                    relations: iter::repeat(None).take(MAX_SLOTS).collect(),
+                    // Relations are allocated lazily, as index nodes may have a fan-out much smaller than MAX_SLOTS
+                    relations: Vec::new(),
                     edit_counter: 1, /* Important for existence to count as an edit, as it cannot be the same as
                                       * non-existence (0) */
                     ref_count: 0 }
@@ -522,6 +585,15 @@ impl ItemEditGuard {
         }
         // Ok! nobody got in our way – Lets do this...
 
+        // Vacate relations for any slots which are absent from the projection for the new head
+        let projected: Vec<SlotId> = self.links.as_ref().unwrap().iter().map(|link| link.slot_id()).collect();
+        let relation_count = inner.items[self.item_id].as_ref().unwrap().relations.len();
+        for slot_id in 0..relation_count {
+            if !projected.contains(&(slot_id as SlotId)) {
+                inner.set_relation(self.item_id, slot_id as SlotId, None);
+            }
+        }
+
         // record all the projected relations for the new head
         for edge_link in self.links.as_ref().unwrap().iter() {
             match edge_link {
@@ -573,6 +645,40 @@ impl StashIterator {
     }
 }
 
+pub struct TopologicalStashIterator {
+    inner: Arc<Mutex<StashInner>>,
+    order: std::vec::IntoIter<EntityId>,
+}
+
+impl TopologicalStashIterator {
+    fn new(inner: &Arc<Mutex<StashInner>>) -> Self {
+        let order = inner.lock().unwrap().topological_order();
+
+        TopologicalStashIterator { inner: inner.clone(),
+                                   order: order.into_iter(), }
+    }
+}
+
+/// Placeholders, and entities whose heads have since been pruned, are skipped.
+impl Iterator for TopologicalStashIterator {
+    type Item = Head;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = self.inner.lock().unwrap();
+
+        for entity_id in self.order.by_ref() {
+            if let Some(item_id) = inner.get_item_id_for_entity(entity_id) {
+                let item = inner.items[item_id].as_ref().unwrap();
+                if item.head.is_some() {
+                    return Some(item.head.clone());
+                }
+            }
+        }
+
+        None
+    }
+}
+
 /// Rudimentary iterator for the contents of the stash. It operates under the assumptions that:
 /// 1. The contents of the stash may change mid-iteration
 /// 2. We do not wish to visit two Heads bearing the same entity_id twice
@@ -0,0 +1,310 @@
+use crate::{
+    context::Context,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    slab::{
+        EntityId,
+        EntityType,
+        MemoId,
+        MemoRef,
+        SlabId,
+    },
+};
+
+use std::{
+    fmt,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use timer::Delay;
+use tracing::debug;
+
+const TOKEN_VERSION: u64 = 1;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A record of everything a context has seen, such that another context - possibly on another slab, in another
+/// process - may wait until it has seen at least as much before reading. This gives read-your-writes consistency to
+/// a session whose requests land on different slabs.
+///
+/// Tokens are opaque. They are rendered as a short url-safe string with `to_string`, which is suitable for an HTTP
+/// header or cookie, and read back with `parse`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CausalToken {
+    /// The slab which issued the token, and from which the memos may be requested
+    origin: SlabId,
+    /// The stash heads, by index node id
+    heads:  Vec<(u64, Vec<MemoId>)>,
+}
+
+impl CausalToken {
+    /// The number of stash heads referenced by the token
+    pub fn len(&self) -> usize {
+        self.heads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heads.is_empty()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, TOKEN_VERSION);
+        write_varint(&mut bytes, self.origin as u64);
+        write_varint(&mut bytes, self.heads.len() as u64);
+
+        for (id, memo_ids) in self.heads.iter() {
+            write_varint(&mut bytes, *id);
+            write_varint(&mut bytes, memo_ids.len() as u64);
+            for memo_id in memo_ids {
+                // Memo ids are the slab id in the upper half and a counter in the lower, so each half is small
+                write_varint(&mut bytes, memo_id >> 32);
+                write_varint(&mut bytes, memo_id & 0xffff_ffff);
+            }
+        }
+
+        bytes
+    }
+
+    fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let bytes = &mut bytes;
+        if read_varint(bytes)? != TOKEN_VERSION {
+            return None;
+        }
+
+        let origin = read_varint(bytes)?;
+        if origin > SlabId::MAX as u64 {
+            return None;
+        }
+
+        let count = read_varint(bytes)?;
+        let mut heads = Vec::new();
+        for _ in 0..count {
+            let id = read_varint(bytes)?;
+
+            let memo_count = read_varint(bytes)?;
+            let mut memo_ids = Vec::new();
+            for _ in 0..memo_count {
+                let high = read_varint(bytes)?;
+                let low = read_varint(bytes)?;
+                if high > 0xffff_ffff || low > 0xffff_ffff {
+                    return None;
+                }
+                memo_ids.push(high << 32 | low);
+            }
+
+            heads.push((id, memo_ids));
+        }
+
+        if !bytes.is_empty() {
+            return None;
+        }
+
+        Some(CausalToken { origin: origin as SlabId,
+                           heads })
+    }
+}
+
+impl fmt::Display for CausalToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes = self.to_bytes();
+
+        let mut encoded = String::with_capacity((bytes.len() * 4).div_ceil(3));
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+            for i in 0..=chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            }
+        }
+
+        f.write_str(&encoded)
+    }
+}
+
+impl std::str::FromStr for CausalToken {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+        for chunk in s.as_bytes().chunks(4) {
+            if chunk.len() == 1 {
+                return Err(());
+            }
+
+            let mut n = 0u32;
+            for (i, c) in chunk.iter().enumerate() {
+                let value = ALPHABET.iter().position(|a| a == c).ok_or(())? as u32;
+                n |= value << (18 - 6 * i);
+            }
+            for i in 0..chunk.len() - 1 {
+                bytes.push((n >> (16 - 8 * i)) as u8);
+            }
+        }
+
+        CausalToken::from_bytes(&bytes).ok_or(())
+    }
+}
+
+impl Context {
+    /// A token for everything this context has seen so far, including its own writes. Pass it along with the session
+    /// to [`Context::await_token`] on another context before reading there. The token lists the heads of the stash,
+    /// which is compacted first such that little more than the root index remains. Snapshots may not be compacted, so
+    /// theirs lists the stash as it stands.
+    pub async fn causal_token(&self) -> Result<CausalToken, WriteError> {
+        if !self.is_read_only() {
+            self.compact().await?;
+        }
+
+        let heads = self.stash
+                        .iter()
+                        .filter_map(|head| head.entity_id().map(|entity_id| (entity_id.id, head.memo_ids())))
+                        .collect();
+
+        Ok(CausalToken { origin: self.slab.my_ref.slab_id,
+                         heads })
+    }
+
+    /// Wait until this context has applied everything referenced by the token, fetching memos from the slab which
+    /// issued it where necessary. Reads made afterward reflect at least what the issuing context had seen.
+    /// Returns `NotFoundByDeadline` if this can't be done within the timeout.
+    pub async fn await_token(&self, token: &CausalToken, timeout: Duration) -> Result<(), RetrieveError> {
+        let deadline = Instant::now() + timeout;
+        let mut pending: Vec<&(u64, Vec<MemoId>)> = token.heads.iter().collect();
+
+        loop {
+            let mut remaining = Vec::new();
+            for entry in pending {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if !self.try_apply_token_head(token.origin, entry, left).await {
+                    remaining.push(entry);
+                }
+            }
+
+            if remaining.is_empty() {
+                return Ok(());
+            }
+
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                debug!("await_token gave up with {} of {} heads outstanding",
+                       remaining.len(),
+                       token.heads.len());
+                return Err(RetrieveError::NotFoundByDeadline);
+            }
+
+            pending = remaining;
+            Delay::new(left.min(Duration::from_millis(50))).await;
+        }
+    }
+
+    /// Returns true if the head is now reflected in our stash. Memos which are not yet resident are waited for no longer
+    /// than the timeout
+    async fn try_apply_token_head(&self, origin: SlabId, (id, memo_ids): &(u64, Vec<MemoId>), timeout: Duration)
+                                  -> bool {
+        let entity_id = EntityId { id:    *id,
+                                   stype: EntityType::IndexNode, };
+
+        let current = self.stash.get_head(entity_id).memo_ids();
+        if memo_ids.iter().all(|memo_id| current.contains(memo_id)) {
+            return true;
+        }
+
+        let memorefs: Vec<MemoRef> = memo_ids.iter()
+                                             .map(|memo_id| {
+                                                 self.slab.agent.memoref_held_by(*memo_id, Some(entity_id), origin)
+                                             })
+                                             .collect();
+
+        // Fetch them all up front, rather than one by one as the stash happens to need them
+        if !self.slab.fetch_memos(&memorefs, timeout).await {
+            return false;
+        }
+
+        let head = Head::Entity { owning_slab_id: self.slab.my_ref.slab_id,
+                                  entity_id,
+                                  head: memorefs };
+
+        if self.apply_head(&head).await.is_err() {
+            return false;
+        }
+        self.recheck_index_catalog();
+
+        true
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+
+        value |= ((byte & 0x7f) as u64).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::CausalToken;
+    use crate::{
+        error::RetrieveError,
+        Network,
+        Slab,
+    };
+    use std::time::{
+        Duration,
+        Instant,
+    };
+
+    #[test]
+    fn causal_token_encoding() {
+        let token = CausalToken { origin: 7,
+                                  heads:  vec![(u64::MAX, vec![(7u64 << 32) | 1, (7u64 << 32) | 300]),
+                                               (9001, vec![])], };
+
+        let encoded = token.to_string();
+        assert!(encoded.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+        assert_eq!(encoded.parse::<CausalToken>(), Ok(token));
+
+        assert!("".parse::<CausalToken>().is_err());
+        assert!("not a token".parse::<CausalToken>().is_err());
+        assert!(encoded[..encoded.len() - 2].parse::<CausalToken>().is_err());
+    }
+
+    #[unbase_test_util::async_test]
+    async fn causal_token_deadline() {
+        let net = Network::create_new_system();
+        let slab = Slab::new(&net);
+        let context = slab.create_context();
+
+        // Nobody has ever heard of this memo
+        let token = CausalToken { origin: 12345,
+                                  heads:  vec![(9001, vec![(12345u64 << 32) | 1])], };
+
+        let start = Instant::now();
+        assert_eq!(context.await_token(&token, Duration::from_millis(100)).await,
+                   Err(RetrieveError::NotFoundByDeadline));
+        assert!(start.elapsed() < Duration::from_millis(500), "overran: {:?}", start.elapsed());
+
+        // A context always satisfies its own token
+        let own = context.causal_token().await.unwrap();
+        context.await_token(&own, Duration::from_millis(100)).await.unwrap();
+    }
+}
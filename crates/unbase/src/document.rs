@@ -0,0 +1,275 @@
+//! JSON documents, stored as a tree of entities.
+//!
+//! Each JSON object or array becomes a [`Record`](crate::slab::EntityType::Record) entity. Scalar members are stored
+//! as values containing their JSON text. Nested objects and arrays become child entities, which are linked via a
+//! relation slot derived from their key, and referenced by a `{"$ref":<slot>}` value under the member's key. Every leaf
+//! thus lives in an ordinary entity value, and may be edited individually with the usual causal guarantees.
+//!
+//! Changes to the structure of a document are single-writer: adding an object or array member, or appending to an
+//! array, must not be done concurrently by contexts which have yet to see each other's changes to the same node.
+//! Slots are assigned with respect to the children the writing context knows of, so concurrently added children may
+//! be assigned the same slot, and concurrent appends write the same member of the array. Each node may have at most
+//! `MAX_SLOTS` object or array members, beyond which writes fail with `DocumentError::TooManyChildren`.
+
+use crate::{
+    context::Context,
+    entity::Entity,
+    error::{
+        DocumentError,
+        RetrieveError,
+        WriteError,
+    },
+    slab::{
+        hash_id,
+        RelationSet,
+        SlotId,
+        MAX_SLOTS,
+    },
+};
+
+use futures::future::{
+    BoxFuture,
+    FutureExt,
+};
+use serde_json::{
+    Map,
+    Value,
+};
+use std::collections::HashMap;
+
+/// Reserved key denoting whether the entity is an "object" or an "array"
+const KIND_KEY: &str = "_doc";
+/// Reserved key recording the length of an array
+const LEN_KEY: &str = "_len";
+const REF_KEY: &str = "$ref";
+
+impl Context {
+    /// Store a JSON document, returning the entity for its root. The document must be an object or an array.
+    pub async fn put_document(&self, document: &Value) -> Result<Entity, WriteError> {
+        put_container(self, document).await
+    }
+}
+
+impl Entity {
+    /// Reassemble the JSON document rooted at this entity
+    pub async fn to_document(&mut self) -> Result<Value, RetrieveError> {
+        self.context.mut_update_record_head_for_consistency(&mut self.head).await?;
+
+        let values = self.head.project_values(&self.context.slab).await?;
+        let kind = values.get(KIND_KEY)
+                         .cloned()
+                         .ok_or(RetrieveError::DocumentError(DocumentError::NotADocument))?;
+
+        let mut members = Vec::new();
+        for (key, text) in values.iter() {
+            if let Some(key) = decode_key(key) {
+                members.push((key, self.resolve_member(text).await?));
+            }
+        }
+
+        if kind == "array" {
+            let len: usize = values.get(LEN_KEY).and_then(|l| l.parse().ok()).unwrap_or(0);
+            let mut array = vec![Value::Null; len];
+
+            for (key, value) in members {
+                if let Some(slot) = key.parse::<usize>().ok().and_then(|i| array.get_mut(i)) {
+                    *slot = value;
+                }
+            }
+
+            Ok(Value::Array(array))
+        } else {
+            Ok(Value::Object(members.into_iter().collect::<Map<String, Value>>()))
+        }
+    }
+
+    /// Retrieve the value at a dot-separated path within this document, eg: "address.lines.0"
+    pub async fn get_path(&mut self, path: &str) -> Result<Option<Value>, RetrieveError> {
+        let (mut node, key) = match self.walk_path(path).await? {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+
+        match node.get_value(&encode_key(&key)).await? {
+            Some(text) => Ok(Some(node.resolve_member(&text).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the value at a dot-separated path within this document. All path segments other than the last must
+    /// already exist. Objects and arrays are stored as new child entities. Appending to an array sets the member at
+    /// its current length. Adding children and appending are single-writer; see the module documentation.
+    pub async fn set_path(&mut self, path: &str, value: &Value) -> Result<(), WriteError> {
+        let (mut node, key) =
+            self.walk_path(path)
+                .await?
+                .ok_or_else(|| WriteError::DocumentError(DocumentError::InvalidPath(path.to_string())))?;
+
+        // Bring the node up to date first, as writes made via another handle would otherwise be concurrent with ours
+        let values = node.get_values().await?;
+
+        // Find a slot for the child before writing anything, so that a node which has no room is left as it was
+        let slot_id = match value {
+            Value::Object(_) | Value::Array(_) => Some(slot_for(&encode_key(&key), &slot_owners(&values))?),
+            _ => None,
+        };
+
+        if values.get(KIND_KEY).map(|k| k.as_str()) == Some("array") {
+            let len: usize = values.get(LEN_KEY).and_then(|l| l.parse().ok()).unwrap_or(0);
+            match key.parse::<usize>() {
+                Ok(index) if index < len => {},
+                Ok(index) if index == len => node.set_value(LEN_KEY, &(len + 1).to_string()).await?,
+                _ => return Err(WriteError::DocumentError(DocumentError::InvalidPath(path.to_string()))),
+            }
+        }
+
+        let text = match slot_id {
+            Some(slot_id) => {
+                let child = put_container(&node.context, value).await?;
+
+                // The relation must precede the reference, so that anyone who sees the latter also sees the former
+                node.set_relation(slot_id, &child).await?;
+                ref_text(slot_id)
+            },
+            None => value.to_string(),
+        };
+
+        node.set_value(&encode_key(&key), &text).await
+    }
+
+    /// Follow all but the last segment of a path, returning the entity which contains the last segment
+    async fn walk_path(&mut self, path: &str) -> Result<Option<(Entity, String)>, RetrieveError> {
+        let mut segments: Vec<&str> = path.split('.').collect();
+        let last = segments.pop().unwrap().to_string();
+
+        let mut node = self.clone();
+        for segment in segments {
+            let text = match node.get_value(&encode_key(segment)).await? {
+                Some(text) => text,
+                None => return Ok(None),
+            };
+
+            node = match parse_ref(&text) {
+                Some(slot_id) => {
+                    match node.get_relation(slot_id).await? {
+                        Some(child) => child,
+                        None => return Ok(None),
+                    }
+                },
+                None => return Ok(None),
+            };
+        }
+
+        Ok(Some((node, last)))
+    }
+
+    fn resolve_member<'a>(&'a mut self, text: &'a str) -> BoxFuture<'a, Result<Value, RetrieveError>> {
+        async move {
+            if let Some(slot_id) = parse_ref(text) {
+                return match self.get_relation(slot_id).await? {
+                    Some(mut child) => child.to_document().await,
+                    None => Ok(Value::Null),
+                };
+            }
+
+            serde_json::from_str(text).map_err(|_| RetrieveError::DocumentError(DocumentError::NotADocument))
+        }.boxed()
+    }
+}
+
+fn put_container<'a>(context: &'a Context, document: &'a Value) -> BoxFuture<'a, Result<Entity, WriteError>> {
+    async move {
+        let members: Vec<(String, &Value)> = match document {
+            Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            Value::Array(array) => array.iter().enumerate().map(|(i, v)| (i.to_string(), v)).collect(),
+            _ => return Err(WriteError::DocumentError(DocumentError::NotAContainer)),
+        };
+
+        // Refuse an oversized node before any of its children are written
+        if members.iter().filter(|(_, value)| value.is_object() || value.is_array()).count() > MAX_SLOTS {
+            return Err(WriteError::DocumentError(DocumentError::TooManyChildren));
+        }
+
+        let mut vals = HashMap::new();
+        let mut relations = RelationSet::empty();
+        let mut owners = HashMap::new();
+
+        match document {
+            Value::Array(array) => {
+                vals.insert(KIND_KEY.to_string(), "array".to_string());
+                vals.insert(LEN_KEY.to_string(), array.len().to_string());
+            },
+            _ => {
+                vals.insert(KIND_KEY.to_string(), "object".to_string());
+            },
+        }
+
+        for (key, value) in members {
+            let key = encode_key(&key);
+            let text = match value {
+                Value::Object(_) | Value::Array(_) => {
+                    let slot_id = slot_for(&key, &owners)?;
+                    owners.insert(slot_id, key.clone());
+                    relations.insert(slot_id, put_container(context, value).await?.id);
+                    ref_text(slot_id)
+                },
+                _ => value.to_string(),
+            };
+
+            vals.insert(key, text);
+        }
+
+        // The relations are written along with the references to them, in a single memo
+        Entity::new_with_relations(context, vals, relations).await
+    }.boxed()
+}
+
+/// Document keys beginning with an underscore are escaped with an additional one, so as not to collide with the
+/// reserved keys
+fn encode_key(key: &str) -> String {
+    if key.starts_with('_') {
+        format!("_{}", key)
+    } else {
+        key.to_string()
+    }
+}
+
+/// Returns None for reserved keys
+fn decode_key(key: &str) -> Option<String> {
+    if key.starts_with("__") {
+        Some(key[1..].to_string())
+    } else if key.starts_with('_') {
+        None
+    } else {
+        Some(key.to_string())
+    }
+}
+
+fn ref_text(slot_id: SlotId) -> String {
+    let mut map = Map::new();
+    map.insert(REF_KEY.to_string(), Value::from(slot_id));
+    Value::Object(map).to_string()
+}
+
+fn parse_ref(text: &str) -> Option<SlotId> {
+    match serde_json::from_str::<Value>(text).ok()? {
+        Value::Object(map) => map.get(REF_KEY)?.as_u64().map(|s| s as SlotId),
+        _ => None,
+    }
+}
+
+/// The relation slots of a document node, and the keys which refer to them
+fn slot_owners(values: &HashMap<String, String>) -> HashMap<SlotId, String> {
+    values.iter().filter_map(|(key, text)| parse_ref(text).map(|slot_id| (slot_id, key.clone()))).collect()
+}
+
+/// The relation slot for the child under the given key. Slots are derived from the key itself, and keys whose slots
+/// collide are probed onward from there. This is only deterministic with respect to the children the writing context
+/// knows of, hence adding children being single-writer.
+fn slot_for(key: &str, owners: &HashMap<SlotId, String>) -> Result<SlotId, WriteError> {
+    let start = hash_id(&[key.as_bytes()]) as usize;
+
+    (0..MAX_SLOTS).map(|i| ((start + i) % MAX_SLOTS) as SlotId)
+                  .find(|slot_id| owners.get(slot_id).is_none_or(|owner| owner == key))
+                  .ok_or(WriteError::DocumentError(DocumentError::TooManyChildren))
+}
@@ -5,6 +5,7 @@ use crate::{
         WriteError,
     },
     head::Head,
+    schema::migration::VERSION_KEY,
     slab::{
         EdgeSet,
         EntityId,
@@ -14,6 +15,7 @@ use crate::{
         RelationSet,
         SlabHandle,
         SlotId,
+        TypeId,
     },
 };
 
@@ -38,20 +40,56 @@ pub struct Entity {
 /// anywhere other than user code, otherwise we will create a cycle and thus a memory leak
 impl Entity {
     pub async fn new(context: &Context, vals: HashMap<String, String>) -> Result<Entity, WriteError> {
+        let id = context.slab.generate_entity_id(EntityType::Record);
+
+        Self::create(context, id, vals, RelationSet::empty()).await
+    }
+
+    /// Create a new entity with relations as well as values, in a single memo
+    pub(crate) async fn new_with_relations(context: &Context,
+                                           vals: HashMap<String, String>,
+                                           relations: RelationSet)
+                                           -> Result<Entity, WriteError> {
+        let id = context.slab.generate_entity_id(EntityType::Record);
+
+        Self::create(context, id, vals, relations).await
+    }
+
+    /// Create a new entity of a user-defined type. The values are validated against the Schema registered for that
+    /// type, which must be resolvable by this context
+    pub async fn new_typed(context: &Context, type_id: TypeId, vals: HashMap<String, String>) -> Result<Entity, WriteError> {
+        let schema = context.require_schema(type_id).await?;
+        schema.validate_values(&vals)?;
+
+        let mut vals = vals;
+        vals.insert(VERSION_KEY.to_string(), schema.version.to_string());
+
+        let id = context.slab.generate_entity_id(EntityType::Custom(type_id));
+
+        Self::create(context, id, vals, RelationSet::empty()).await
+    }
+
+    pub(crate) async fn create(context: &Context,
+                               id: EntityId,
+                               vals: HashMap<String, String>,
+                               relations: RelationSet)
+                               -> Result<Entity, WriteError> {
+        context.check_writable()?;
+
         let slab: &SlabHandle = &context.slab;
-        let id = slab.generate_entity_id(EntityType::Record);
 
         debug!("Entity({}).new()", id);
 
         let head = slab.new_memo(Some(id),
                                  Head::Null,
-                                 MemoBody::FullyMaterialized { v: vals,
-                                                               r: RelationSet::empty(),
+                                 MemoBody::FullyMaterialized { v: vals.clone(),
+                                                               r: relations,
                                                                e: EdgeSet::empty(),
                                                                t: id.stype.clone(), })
                        .to_head();
 
         context.update_indices(id, &head).await?;
+        context.update_secondary_indices(&Head::Null, &head, &vals).await?;
 
         let handle = Entity { id,
                               head,
@@ -60,6 +98,36 @@ impl Entity {
         Ok(handle)
     }
 
+    /// Retrieve the entity for a natural key within a namespace, creating it if this context does not yet know of it.
+    /// Because the EntityId is derived from the namespace and key, concurrent creations on different slabs converge on
+    /// a single entity. See [`EntityId::from_natural_key`](crate::slab::EntityId::from_natural_key)
+    pub async fn get_or_create_by_key(context: &Context, namespace: &str, key: &str) -> Result<Entity, WriteError> {
+        let id = EntityId::from_natural_key(namespace, key);
+
+        if let Some(entity) = context.get_entity(id).await? {
+            return Ok(entity);
+        }
+
+        context.check_writable()?;
+
+        debug!("Entity({}).get_or_create_by_key({}, {})", id, namespace, key);
+
+        // The genesis memo must be identical on every slab, so it carries no values
+        let head = context.slab
+                          .new_genesis_memo(id,
+                                            MemoBody::FullyMaterialized { v: HashMap::new(),
+                                                                          r: RelationSet::empty(),
+                                                                          e: EdgeSet::empty(),
+                                                                          t: id.stype, })
+                          .to_head();
+
+        context.update_indices(id, &head).await?;
+
+        Ok(Entity { id,
+                    head,
+                    context: context.clone() })
+    }
+
     pub async fn new_blank(context: &Context) -> Result<Entity, WriteError> {
         Self::new(context, HashMap::new()).await
     }
@@ -80,9 +148,31 @@ impl Entity {
                        copy,
                        self.head);
 
+        if let EntityType::Custom(type_id) = self.id.stype {
+            if let Some(schema) = self.context.get_schema(type_id).await? {
+                return self.context.get_typed_value(&self.head, &schema, key).await;
+            }
+        }
+
         self.head.get_value(&self.context.slab, key).await
     }
 
+    /// Project all values of the entity
+    pub async fn get_values(&mut self) -> Result<HashMap<String, String>, RetrieveError> {
+        self.context.mut_update_record_head_for_consistency(&mut self.head).await?;
+
+        if let EntityType::Custom(type_id) = self.id.stype {
+            if let Some(schema) = self.context.get_schema(type_id).await? {
+                let mut values = self.context.get_typed_values(&self.head, &schema).await?;
+                values.remove(VERSION_KEY);
+
+                return Ok(values);
+            }
+        }
+
+        self.head.project_values(&self.context.slab).await
+    }
+
     pub async fn get_edge(&mut self, key: SlotId) -> Result<Option<Entity>, RetrieveError> {
         self.context.mut_update_record_head_for_consistency(&mut self.head).await?;
 
@@ -102,16 +192,37 @@ impl Entity {
     }
 
     pub async fn set_value(&mut self, key: &str, value: &str) -> Result<(), WriteError> {
-        self.head.set_value(&self.context.slab, key, value).await?;
+        self.context.check_writable()?;
+
+        let mut vals = HashMap::new();
+        vals.insert(key.to_string(), value.to_string());
+
+        if let EntityType::Custom(type_id) = self.id.stype {
+            let schema = self.context.require_schema(type_id).await?;
+            schema.validate_value(key, value)?;
+
+            // Record the schema version this write was made under, so that it may be migrated later
+            vals.insert(VERSION_KEY.to_string(), schema.version.to_string());
+        }
+
+        let previous = self.head.clone();
+        self.head.set_values(&self.context.slab, vals.clone()).await?;
 
         // Update our indices before returning to ensure that subsequence queries against this context are
         // self-consistent
         self.context.update_indices(self.id, &self.head).await?;
+        self.context.update_secondary_indices(&previous, &self.head, &vals).await?;
 
         Ok(())
     }
 
     pub async fn set_relation(&mut self, key: SlotId, relation: &Self) -> Result<(), WriteError> {
+        self.context.check_writable()?;
+
+        if let EntityType::Custom(type_id) = self.id.stype {
+            self.context.require_schema(type_id).await?.validate_relation(key, relation.id)?;
+        }
+
         self.head.set_relation(&self.context.slab, key, &relation.head).await?;
 
         // Update our indices before returning to ensure that subsequence queries against this context are
@@ -1,3 +1,12 @@
+use crate::{
+    schema::ValueType,
+    slab::{
+        EntityType,
+        SlotId,
+        TypeId,
+    },
+};
+
 #[derive(PartialEq, Debug)]
 pub enum RetrieveError {
     NotFound,
@@ -8,6 +17,9 @@ pub enum RetrieveError {
     SlabError,
     MemoLineageError,
     WriteError(Box<WriteError>),
+    DocumentError(DocumentError),
+    /// Serialized data, such as a saved context, which could not be read
+    Malformed,
 }
 
 #[derive(PartialEq, Debug)]
@@ -22,6 +34,43 @@ pub enum WriteError {
     RetrieveError(Box<RetrieveError>),
     // This is silly. TODO - break this cycle and remove the Box
     BadTarget,
+    SchemaViolation(SchemaViolation),
+    DocumentError(DocumentError),
+    /// The context is a snapshot, and may not be written to
+    ReadOnly,
+    IndexShapeError(IndexShapeError),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum IndexShapeError {
+    /// The shape addresses fewer bits than the key has, so that distinct keys would share an entry
+    TooNarrow { shape_bits: usize, key_bits: usize },
+}
+
+#[derive(PartialEq, Debug)]
+pub enum SchemaViolation {
+    SchemaNotFound(TypeId),
+    UnknownField(String),
+    InvalidValue { field: String, expected: ValueType },
+    UnknownRelation(SlotId),
+    InvalidRelationTarget { slot_id: SlotId, target: EntityType },
+}
+
+#[derive(PartialEq, Debug)]
+pub enum DocumentError {
+    NotADocument,
+    NotAContainer,
+    /// A document node may have no more than `MAX_SLOTS` object or array members
+    TooManyChildren,
+    InvalidPath(String),
+}
+
+/// Errors in a textual query. Positions are byte offsets into the query text.
+#[derive(PartialEq, Debug)]
+pub enum QueryError {
+    Parse { position: usize, message: String },
+    Plan { position: usize, message: String },
+    RetrieveError(RetrieveError),
 }
 
 #[derive(PartialEq, Debug)]
@@ -34,11 +83,26 @@ impl core::convert::From<()> for ObserveError {
         ObserveError::Unknown
     }
 }
+impl core::convert::From<SchemaViolation> for WriteError {
+    fn from(violation: SchemaViolation) -> Self {
+        WriteError::SchemaViolation(violation)
+    }
+}
+impl core::convert::From<IndexShapeError> for WriteError {
+    fn from(error: IndexShapeError) -> Self {
+        WriteError::IndexShapeError(error)
+    }
+}
 impl core::convert::From<RetrieveError> for WriteError {
     fn from(error: RetrieveError) -> Self {
         WriteError::RetrieveError(Box::new(error))
     }
 }
+impl core::convert::From<RetrieveError> for QueryError {
+    fn from(error: RetrieveError) -> Self {
+        QueryError::RetrieveError(error)
+    }
+}
 impl core::convert::From<WriteError> for RetrieveError {
     fn from(error: WriteError) -> Self {
         RetrieveError::WriteError(Box::new(error))
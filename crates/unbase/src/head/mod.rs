@@ -25,6 +25,7 @@ use crate::{
 
 use std::{
     collections::{
+        BTreeMap,
         HashMap,
         VecDeque,
     },
@@ -94,6 +95,17 @@ impl Head {
             .to_head()
     }
 
+    /// Create an index node with a deterministic id. Every slab which creates this node does so with the same genesis
+    /// memo, so concurrently created copies of the node are one and the same
+    pub fn new_index_with_id(slab: &SlabHandle, entity_id: EntityId, values: HashMap<String, String>) -> Head {
+        slab.new_genesis_memo(entity_id,
+                              MemoBody::FullyMaterialized { v: values,
+                                                            r: RelationSet::empty(),
+                                                            e: EdgeSet::empty(),
+                                                            t: EntityType::IndexNode, })
+            .to_head()
+    }
+
     #[tracing::instrument]
     pub async fn mut_apply_memoref(&mut self, new: &MemoRef, slab: &SlabHandle) -> Result<bool, WriteError> {
         // Conditionally add the new memoref only if it descends any memorefs in the head
@@ -387,11 +399,20 @@ impl Head {
     /// Notify whomever needs to know that a new entity has been created
     #[tracing::instrument]
     pub async fn get_value(&mut self, slab: &SlabHandle, key: &str) -> Result<Option<String>, RetrieveError> {
+        self.get_value_with(slab, key, |_| {}).await
+    }
+
+    /// Like get_value, but reshaping the values of each memo with the provided closure before they are considered
+    pub async fn get_value_with<F>(&self, slab: &SlabHandle, key: &str, reshape: F) -> Result<Option<String>, RetrieveError>
+        where F: Fn(&mut HashMap<String, String>)
+    {
         // TODO: consider creating a consolidated projection routine for most/all uses
         let mut memostream = self.causal_memo_stream(slab.clone()).boxed();
         while let Some(memo) = memostream.next().await {
             // println!("# \t\\ Considering Memo {}", memo.id );
-            if let Some((values, materialized)) = memo?.get_values() {
+            if let Some((mut values, materialized)) = memo?.get_values() {
+                reshape(&mut values);
+
                 if let Some(v) = values.get(key) {
                     return Ok(Some(v.clone()));
                 } else if materialized {
@@ -403,6 +424,57 @@ impl Head {
         Err(RetrieveError::MemoLineageError)
     }
 
+    /// Project all values based on the causal history of this head
+    pub async fn project_values(&self, slab: &SlabHandle) -> Result<HashMap<String, String>, RetrieveError> {
+        self.project_values_with(slab, |_| {}).await
+    }
+
+    /// Project all values based on the causal history of this head, reshaping the values of each memo with the
+    /// provided closure before they are considered
+    pub async fn project_values_with<F>(&self, slab: &SlabHandle, reshape: F) -> Result<HashMap<String, String>, RetrieveError>
+        where F: Fn(&mut HashMap<String, String>)
+    {
+        let mut values = HashMap::new();
+
+        let mut memostream = self.causal_memo_stream(slab.clone());
+        while let Some(memo) = memostream.next().await {
+            if let Some((mut memo_values, materialized)) = memo?.get_values() {
+                reshape(&mut memo_values);
+
+                for (key, value) in memo_values {
+                    // Only consider the keys which were not set by a descendent memo
+                    values.entry(key).or_insert(value);
+                }
+
+                if materialized {
+                    break;
+                }
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Project all relations based on the causal history of this head
+    pub async fn project_relations(&self, slab: &SlabHandle) -> Result<RelationSet, RetrieveError> {
+        let mut relations = RelationSet::empty();
+
+        let mut memostream = self.causal_memo_stream(slab.clone());
+        while let Some(memo) = memostream.next().await {
+            if let Some((memo_relations, materialized)) = memo?.get_relations() {
+                for (slot_id, maybe_entity_id) in memo_relations.0 {
+                    relations.0.entry(slot_id).or_insert(maybe_entity_id);
+                }
+
+                if materialized {
+                    break;
+                }
+            }
+        }
+
+        Ok(relations)
+    }
+
     pub async fn get_relation(&mut self, slab: &SlabHandle, key: SlotId) -> Result<Option<EntityId>, RetrieveError> {
         // println!("# Entity({}).get_relation({})",self.id,key);
 
@@ -431,36 +503,26 @@ impl Head {
     }
 
     pub async fn get_edge(&mut self, slab: &SlabHandle, key: SlotId) -> Result<Option<Head>, RetrieveError> {
-        let mut memostream = self.causal_memo_stream(slab.clone());
-
-        while let Some(memo) = memostream.next().await {
-            let memo = memo?;
-            if let Some((edges, materialized)) = memo.get_edges() {
-                debug!("# \t\\ Considering Memo {}, Head: {:?}, Relations: {:?}",
-                       memo.id,
-                       memo.get_parent_head(),
-                       edges);
+        let (mut edges, lineage_ended) = self.project_merged_edges(slab, Some(key)).await?;
 
-                if let Some(head) = edges.get(&key) {
-                    // TODO POSTMERGE this is likely buggy - shouldn't we be looking at all of the memorefs in the head
-                    // in case of concurrencies?
-
-                    return Ok(Some(head.clone()));
-                } else if materialized {
-                    debug!("\n# \t\\ Not Found (materialized)");
-                    return Ok(None);
-                }
-            }
+        match edges.remove(&key) {
+            Some(head) => Ok(Some(head)),
+            None if lineage_ended => {
+                debug!("Not Found");
+                Err(RetrieveError::MemoLineageError)
+            },
+            None => Ok(None),
         }
-
-        debug!("Not Found");
-        Err(RetrieveError::MemoLineageError)
     }
 
     pub async fn set_value(&mut self, slab: &SlabHandle, key: &str, value: &str) -> Result<(), WriteError> {
         let mut vals = HashMap::new();
         vals.insert(key.to_string(), value.to_string());
 
+        self.set_values(slab, vals).await
+    }
+
+    pub async fn set_values(&mut self, slab: &SlabHandle, vals: HashMap<String, String>) -> Result<(), WriteError> {
         let entity_id = self.entity_id();
 
         // TODO - do this in a single swap? (fairly certain that requires unsafe)
@@ -544,113 +606,161 @@ impl Head {
     // TODO: Consider calculating deltas during memoref application,
     //       and use that to perform a minimum cost entity_head_link edit
 
-    // TODO: This projection method is probably wrong, as it does not consider how to handle concurrent edge-setting
-    //       this problem applies to causal_memo_stream itself really, insofar as it should return sets of concurrent
-    // memos to be merged rather than individual memos This in turn raises questions about how relations should be
-    // merged
+    // TODO: Relation projection does not yet consider concurrent relation-setting the way edge projection does.
+    //       This raises questions about how relations should be merged
 
     /// Project all edge links based only on the causal history of this head.
     /// The name is pretty gnarly, and this is very ripe for refactoring, but at least it says what it does.
+    /// Slots which were never set in the causal history are omitted, so the result is only as wide as the edges in use.
     pub async fn project_all_edge_links_including_empties(&self, slab: &SlabHandle) -> Result<Vec<EdgeLink>, RetrieveError> {
-        let mut edge_links: Vec<Option<EdgeLink>> = Vec::with_capacity(MAX_SLOTS);
-
-        // None is an indication that we've not yet visited this slot, and that it is thus eligible for setting
-        for _ in 0..MAX_SLOTS as usize {
-            edge_links.push(None);
-        }
+        let (edges, _) = self.project_merged_edges(slab, None).await?;
 
-        let mut memostream = self.causal_memo_stream(slab.clone());
-        while let Some(memo) = memostream.next().await {
-            match memo?.body {
-                MemoBody::FullyMaterialized { e: ref edgeset, .. } => {
-                    // Iterate over all the entries in this EdgeSet
-                    for (slot_id, rel_head) in &edgeset.0 {
-                        // Only consider the non-visited slots
-                        if let None = edge_links[*slot_id as usize] {
-                            edge_links[*slot_id as usize] = Some(match *rel_head {
-                                                                     Head::Null => EdgeLink::Vacant { slot_id: *slot_id },
-                                                                     _ => {
-                                                                         EdgeLink::Occupied { slot_id: *slot_id,
-                                                                                              head:    rel_head.clone(), }
-                                                                     },
-                                                                 });
-                        }
-                    }
-
-                    break;
-                    // Fully Materialized memo means we're done here
-                },
-                MemoBody::Edge(ref r) => {
-                    for (slot_id, rel_head) in r.iter() {
-                        // Only consider the non-visited slots
-                        if let None = edge_links[*slot_id as usize] {
-                            edge_links[*slot_id as usize] = Some(match *rel_head {
-                                                                     Head::Null => EdgeLink::Vacant { slot_id: *slot_id },
-                                                                     _ => {
-                                                                         EdgeLink::Occupied { slot_id: *slot_id,
-                                                                                              head:    rel_head.clone(), }
-                                                                     },
-                                                                 })
-                        }
+        Ok(edges.into_iter()
+                .map(|(slot_id, head)| {
+                    match head {
+                        Head::Null => EdgeLink::Vacant { slot_id },
+                        head => EdgeLink::Occupied { slot_id, head },
                     }
-                },
-                _ => {},
-            }
-        }
-
-        let out: Vec<EdgeLink> = edge_links.iter()
-                                           .enumerate()
-                                           .map(|(slot_id, maybe_link)| {
-                                               // Fill in the non-visited links with vacants
-                                               match maybe_link {
-                                                   None => EdgeLink::Vacant { slot_id: slot_id as SlotId, },
-                                                   Some(ref link) => link.clone(),
-                                               }
-                                           })
-                                           .collect();
-
-        Ok(out)
+                })
+                .collect())
     }
 
     /// Contextualized projection of edges for occupied slots
     pub async fn project_occupied_edges(&self, slab: &SlabHandle) -> Result<Vec<EdgeLink>, RetrieveError> {
-        let mut visited = [false; MAX_SLOTS];
-        let mut edge_links: Vec<EdgeLink> = Vec::new();
+        let (edges, _) = self.project_merged_edges(slab, None).await?;
 
-        let mut memostream = self.causal_memo_stream(slab.clone());
-        'memo: while let Some(memo) = memostream.next().await {
-            let memo = memo?;
+        Ok(edges.into_iter()
+                .filter(|(_, head)| head.is_some())
+                .map(|(slot_id, head)| EdgeLink::Occupied { slot_id, head })
+                .collect())
+    }
+
+    /// Project the edges of every slot, or only of the given one, merging the edges set on concurrent branches.
+    ///
+    /// The edge of a slot is that set by the youngest memo on each branch of the causal history which sets the slot at
+    /// all. Where concurrent branches set it to heads of the same entity, as when two slabs insert into the same
+    /// index node, those heads are merged, so that neither branch's writes to the entity go missing. Where they set it
+    /// to different entities (or vacate it) the edge set by the memo with the greatest id prevails, so that every
+    /// slab settles on the same one. Also returns whether any branch of the history ended without being materialized.
+    async fn project_merged_edges(&self, slab: &SlabHandle, only: Option<SlotId>)
+                                  -> Result<(BTreeMap<SlotId, Head>, bool), RetrieveError> {
+        // Collect the causal history, back to the nearest materialized memo on each branch. When only one slot is
+        // wanted, a branch may also stop at the nearest memo which sets it
+        let mut memos: HashMap<MemoId, Memo> = HashMap::new();
+        let mut lineage_ended = false;
+        let mut stack = self.to_vec();
+
+        while let Some(memoref) = stack.pop() {
+            if memos.contains_key(&memoref.id) {
+                continue;
+            }
+
+            let memo = memoref.get_memo(slab.clone()).await?;
 
-            let (edgeset, last) = match memo.body {
-                MemoBody::FullyMaterialized { e: ref edgeset, .. } => (edgeset, true),
-                MemoBody::Edge(ref edgeset) => (edgeset, false),
-                _ => continue 'memo,
+            let stop = match memo.get_edges() {
+                Some((_, true)) => true,
+                Some((edges, false)) => only.is_some_and(|slot_id| edges.iter().any(|(s, _)| *s == slot_id)),
+                None => false,
             };
 
-            for (slot_id, rel_head) in edgeset.iter() {
-                // TODO POSTMERGE - I believe this is wrong. Need to consider concurrencies.
-                //                  Also, should probably stop whenever we hit a FullyMaterialized
+            if !stop {
+                match memo.get_parent_head() {
+                    Head::Null => lineage_ended = true,
+                    parents => stack.extend(parents.to_vec()),
+                }
+            }
 
-                // Only consider the non-visited slots
-                if !visited[*slot_id as usize] {
-                    visited[*slot_id as usize] = true;
+            memos.insert(memo.id, memo);
+        }
 
-                    match *rel_head {
-                        Head::Entity { .. } | Head::Anonymous { .. } => {
-                            edge_links.push(EdgeLink::Occupied { slot_id: *slot_id,
-                                                                 head:    rel_head.clone(), });
-                        },
-                        Head::Null => {},
-                    };
+        // Visit each memo after all of its descendants, such that we know which slots they have already set
+        let mut descendants: HashMap<MemoId, usize> = HashMap::new();
+        for memo in memos.values() {
+            for parent in memo.parents.iter() {
+                if memos.contains_key(&parent.id) {
+                    *descendants.entry(parent.id).or_default() += 1;
+                }
+            }
+        }
+
+        let mut ready: Vec<MemoId> = memos.keys().filter(|id| !descendants.contains_key(id)).cloned().collect();
+        let mut shadowed: HashMap<MemoId, SlotSet> = HashMap::new();
+        let mut candidates: BTreeMap<SlotId, Vec<(MemoId, Head)>> = BTreeMap::new();
+
+        while let Some(memo_id) = ready.pop() {
+            let memo = &memos[&memo_id];
+            let mut shadow = shadowed.remove(&memo_id).unwrap_or_default();
+
+            if let Some((edges, materialized)) = memo.get_edges() {
+                for (slot_id, head) in edges.iter() {
+                    if only.is_none_or(|only| only == *slot_id) && !shadow.contains(*slot_id) {
+                        candidates.entry(*slot_id).or_default().push((memo_id, head.clone()));
+                    }
+                    shadow.insert(*slot_id);
+                }
+
+                // A materialized memo speaks for every slot, set or not
+                if materialized {
+                    shadow = SlotSet::full();
                 }
             }
 
-            if last {
-                break;
+            for parent in memo.parents.iter() {
+                if let Some(remaining) = descendants.get_mut(&parent.id) {
+                    shadowed.entry(parent.id).or_default().union(&shadow);
+
+                    *remaining -= 1;
+                    if *remaining == 0 {
+                        ready.push(parent.id);
+                    }
+                }
             }
         }
 
-        Ok(edge_links)
+        let mut edges = BTreeMap::new();
+        for (slot_id, mut concurrent) in candidates {
+            concurrent.sort_by_key(|(memo_id, _)| std::cmp::Reverse(*memo_id));
+
+            let mut iter = concurrent.into_iter().map(|(_, head)| head);
+            let mut merged = iter.next().expect("at least one candidate per slot");
+
+            if let Some(entity_id) = merged.entity_id() {
+                for head in iter {
+                    if head.entity_id() == Some(entity_id) {
+                        merged.mut_apply(&head, slab).await?;
+                    }
+                }
+            }
+
+            debug!("# \t\\ Slot {} of {:?}: {:?}", slot_id, self.entity_id(), merged);
+            edges.insert(slot_id, merged);
+        }
+
+        Ok((edges, lineage_ended))
+    }
+}
+
+/// A set of slot ids, one bit per slot
+#[derive(Clone, Copy, Default)]
+struct SlotSet([u64; MAX_SLOTS / 64]);
+
+impl SlotSet {
+    fn full() -> Self {
+        SlotSet([u64::MAX; MAX_SLOTS / 64])
+    }
+
+    fn insert(&mut self, slot_id: SlotId) {
+        self.0[slot_id as usize / 64] |= 1 << (slot_id % 64);
+    }
+
+    fn contains(&self, slot_id: SlotId) -> bool {
+        self.0[slot_id as usize / 64] & 1 << (slot_id % 64) != 0
+    }
+
+    fn union(&mut self, other: &SlotSet) {
+        for (word, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *word |= other;
+        }
     }
 }
 
@@ -45,6 +45,7 @@ impl StatefulSerialize for Head {
     }
 }
 
+#[derive(Clone)]
 pub struct HeadSeed<'a> {
     pub dest_slab:      &'a SlabHandle,
     pub origin_slabref: &'a SlabRef,
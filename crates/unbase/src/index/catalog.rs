@@ -0,0 +1,136 @@
+use crate::{
+    context::Context,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    slab::EntityId,
+};
+
+use std::collections::HashMap;
+
+/// The kinds of index which are listed in the catalog
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum IndexKind {
+    Secondary,
+    FullText,
+    Composite,
+    Ordered,
+}
+
+impl IndexKind {
+    fn name(self) -> &'static str {
+        match self {
+            IndexKind::Secondary => "secondary",
+            IndexKind::FullText => "fulltext",
+            IndexKind::Composite => "composite",
+            IndexKind::Ordered => "ordered",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "secondary" => Some(IndexKind::Secondary),
+            "fulltext" => Some(IndexKind::FullText),
+            "composite" => Some(IndexKind::Composite),
+            "ordered" => Some(IndexKind::Ordered),
+            _ => None,
+        }
+    }
+}
+
+/// An index which is listed in the catalog.
+///
+/// Every secondary, full-text, composite and ordered index is listed in a single catalog node with a deterministic
+/// id, such that a write need only read the catalog to learn which indexes it must update, rather than looking up each
+/// index which might concern it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Declared {
+    pub kind:    IndexKind,
+    /// In sorted order
+    pub fields:  Vec<String>,
+    /// The fields whose values are carried by a covering index, other than the indexed fields themselves
+    pub covered: Vec<String>,
+}
+
+impl Declared {
+    /// Whether this is an index of the given kind over the given field alone
+    pub fn is_on(&self, kind: IndexKind, field: &str) -> bool {
+        self.kind == kind && self.fields.len() == 1 && self.fields[0] == field
+    }
+
+    /// Whether a write to the given field may change the entries of this index
+    pub fn is_affected_by(&self, field: &str) -> bool {
+        self.fields.iter().chain(self.covered.iter()).any(|f| f == field)
+    }
+
+    /// List the index in the catalog, creating the catalog if need be
+    pub async fn record(&self, context: &Context) -> Result<(), WriteError> {
+        let catalog_id = EntityId::index_catalog();
+
+        let catalog = match context.index_catalog().await? {
+            Some((head, _)) => head,
+            None => Head::new_index_with_id(&context.slab, catalog_id, HashMap::new()),
+        };
+        let mut catalog = context.apply_head(&catalog).await?;
+
+        let mut name = vec![self.kind.name().to_string()];
+        name.extend(self.fields.iter().cloned());
+
+        let mut entry = HashMap::new();
+        entry.insert(encode_list(&name), encode_list(&self.covered));
+        catalog.set_values(&context.slab, entry).await?;
+
+        context.apply_head(&catalog).await?;
+        context.update_indices(catalog_id, &catalog).await?;
+        context.recheck_index_catalog();
+
+        Ok(())
+    }
+}
+
+/// Read the indexes listed by the given head of the catalog, in order of kind and then fields. Entries which cannot be
+/// read are skipped.
+pub(crate) async fn read_catalog(context: &Context, head: &Head) -> Result<Vec<Declared>, RetrieveError> {
+    let mut declared = Vec::new();
+
+    for (name, covered) in head.project_values(&context.slab).await? {
+        let mut fields = match decode_list(&name) {
+            Some(fields) if !fields.is_empty() => fields,
+            _ => continue,
+        };
+
+        let kind = match IndexKind::from_name(&fields.remove(0)) {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        if let Some(covered) = decode_list(&covered) {
+            declared.push(Declared { kind, fields, covered });
+        }
+    }
+
+    declared.sort_by(|a, b| (a.kind, &a.fields).cmp(&(b.kind, &b.fields)));
+
+    Ok(declared)
+}
+
+/// Encode a list of strings, each prefixed with its length, such that no two lists share an encoding
+pub(crate) fn encode_list(items: &[String]) -> String {
+    items.iter().map(|item| format!("{}:{}", item.len(), item)).collect()
+}
+
+pub(crate) fn decode_list(mut encoded: &str) -> Option<Vec<String>> {
+    let mut items = Vec::new();
+
+    while !encoded.is_empty() {
+        let (len, rest) = encoded.split_once(':')?;
+        let len: usize = len.parse().ok()?;
+
+        items.push(rest.get(..len)?.to_string());
+        encoded = rest.get(len..)?;
+    }
+
+    Some(items)
+}
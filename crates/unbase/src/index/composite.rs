@@ -0,0 +1,246 @@
+use crate::{
+    context::Context,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    index::{
+        catalog::{
+            decode_list,
+            encode_list,
+            Declared,
+            IndexKind,
+        },
+        IndexSecondary,
+    },
+    slab::EntityId,
+};
+
+use std::{
+    collections::HashMap,
+    fmt,
+};
+
+use tracing::debug;
+
+/// A composite index maps the values of several fields together to the entities having those values.
+///
+/// Entries are listed in postings keyed by the combined values, just as an [`IndexSecondary`] lists them by a single
+/// value. A covering index also carries copies of the values of selected fields: each entry leads to a cover node
+/// holding those values, which in turn leads to the entity, so that queries which only need the covered values may
+/// be answered without projecting the entity itself.
+///
+/// Declared composite indexes are listed in the index catalog, such that writes to any of their fields may find them.
+pub struct IndexComposite {
+    /// In sorted order
+    fields:   Vec<String>,
+    covered:  Vec<String>,
+    postings: IndexSecondary,
+}
+
+/// The values carried by a covering index for one entity
+#[derive(Clone, Debug, PartialEq)]
+pub struct Covered {
+    pub entity_id: EntityId,
+    pub values:    HashMap<String, String>,
+}
+
+impl IndexComposite {
+    /// Create the composite index over the given fields, covering the values of `covered`, or open it if it already
+    /// exists. The covered fields of an existing index are left as they were.
+    pub async fn declare(context: &Context, fields: &[&str], covered: &[&str]) -> Result<IndexComposite, WriteError> {
+        if let Some(index) = Self::open(context, fields).await? {
+            return Ok(index);
+        }
+
+        let fields = sorted(fields);
+        let covered: Vec<String> = covered.iter().map(|f| f.to_string()).collect();
+
+        let mut values = HashMap::new();
+        values.insert("fields".to_string(), encode_list(&fields));
+        values.insert("covered".to_string(), encode_list(&covered));
+
+        let entity_id = composite_id(&fields);
+        let postings = IndexSecondary::declare_with_id(context, &encode_list(&fields), entity_id, values).await?;
+
+        let declared = Declared { kind:    IndexKind::Composite,
+                                  fields:  fields.clone(),
+                                  covered: covered.clone(), };
+        declared.record(context).await?;
+
+        Ok(IndexComposite { fields,
+                            covered,
+                            postings })
+    }
+
+    /// Open the composite index over the given fields, in any order, if one has been declared
+    pub async fn open(context: &Context, fields: &[&str]) -> Result<Option<IndexComposite>, RetrieveError> {
+        let fields = sorted(fields);
+
+        let postings = match IndexSecondary::open_with_id(context, &encode_list(&fields), composite_id(&fields)).await? {
+            Some(postings) => postings,
+            None => return Ok(None),
+        };
+
+        let values = postings.root_head().project_values(&context.slab).await?;
+        let covered = values.get("covered").and_then(|c| decode_list(c)).unwrap_or_default();
+
+        Ok(Some(IndexComposite { fields,
+                                 covered,
+                                 postings }))
+    }
+
+    /// The indexed fields, in sorted order
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// The fields whose values are carried by the index, other than the indexed fields themselves
+    pub fn covered(&self) -> &[String] {
+        &self.covered
+    }
+
+    pub fn is_covering(&self) -> bool {
+        !self.covered.is_empty()
+    }
+
+    /// Whether the index carries the value of the given field
+    pub fn records(&self, field: &str) -> bool {
+        self.is_covering() && self.fields.iter().chain(self.covered.iter()).any(|f| f == field)
+    }
+
+    /// Update the entry for an entity, given its values before and after a write
+    pub async fn update(&mut self, context: &Context, old: &HashMap<String, String>, new: &HashMap<String, String>,
+                        head: Head)
+                        -> Result<(), WriteError> {
+        let entity_id = head.entity_id().ok_or(WriteError::BadTarget)?;
+
+        let old_key = self.key(old);
+        let new_key = self.key(new);
+        debug!("IndexComposite({:?}).update({}, {:?} -> {:?})", self.fields, entity_id, old_key, new_key);
+
+        if let Some(ref old_key) = old_key {
+            if Some(old_key) != new_key.as_ref() {
+                self.postings.remove(context, old_key, entity_id).await?;
+            }
+        }
+
+        let new_key = match new_key {
+            Some(new_key) => new_key,
+            None => return Ok(()),
+        };
+
+        if !self.is_covering() {
+            if old_key.as_ref() == Some(&new_key) {
+                return Ok(());
+            }
+            return self.postings.insert_entry(context, &new_key, entity_id, head).await;
+        }
+
+        let recorded: HashMap<String, String> = self.fields
+                                                    .iter()
+                                                    .chain(self.covered.iter())
+                                                    .filter_map(|f| new.get(f).map(|v| (f.clone(), v.clone())))
+                                                    .collect();
+
+        let unchanged = recorded.iter().all(|(f, v)| old.get(f) == Some(v));
+        if old_key.as_ref() == Some(&new_key) && unchanged {
+            return Ok(());
+        }
+
+        let cover_id = EntityId::index_cover(self.postings.get_root_entity_id(), entity_id);
+        let genesis = Head::new_index_with_id(&context.slab, cover_id, HashMap::new());
+        let mut cover = context.apply_head(&genesis).await?;
+
+        cover.set_values(&context.slab, recorded).await?;
+        cover.set_edge(&context.slab, 0, head);
+        context.apply_head(&cover).await?;
+
+        self.postings.insert_entry(context, &new_key, entity_id, cover).await
+    }
+
+    /// Retrieve the heads of all entities listed under the given values of the indexed fields. As with
+    /// [`IndexSecondary::get`], entries may be stale or missing, so callers must check the values of the entity itself.
+    pub async fn get(&self, context: &Context, values: &HashMap<String, String>) -> Result<Vec<Head>, RetrieveError> {
+        let key = match self.key(values) {
+            Some(key) => key,
+            None => return Ok(Vec::new()),
+        };
+
+        let entries = self.postings.get(context, &key).await?;
+        if !self.is_covering() {
+            return Ok(entries);
+        }
+
+        let mut heads = Vec::with_capacity(entries.len());
+        for mut cover in entries {
+            context.mut_update_index_head_for_consistency(&mut cover).await?;
+            if let Some(head) = cover.get_edge(&context.slab, 0).await? {
+                heads.push(head);
+            }
+        }
+
+        Ok(heads)
+    }
+
+    /// Retrieve the values carried by a covering index for all entities listed under the given values of the indexed
+    /// fields, without projecting the entities themselves. These are the values as of the latest write by a context
+    /// which was aware of the index.
+    pub async fn get_covered(&self, context: &Context, values: &HashMap<String, String>)
+                             -> Result<Vec<Covered>, RetrieveError> {
+        let key = match self.key(values) {
+            Some(key) if self.is_covering() => key,
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut covered = Vec::new();
+        for mut cover in self.postings.get(context, &key).await? {
+            context.mut_update_index_head_for_consistency(&mut cover).await?;
+
+            let entity_id = match cover.get_edge(&context.slab, 0).await? {
+                Some(head) => head.entity_id(),
+                None => None,
+            };
+
+            if let Some(entity_id) = entity_id {
+                covered.push(Covered { entity_id,
+                                       values: cover.project_values(&context.slab).await? });
+            }
+        }
+
+        Ok(covered)
+    }
+
+    /// The posting key for the given values, if all of the indexed fields have one
+    fn key(&self, values: &HashMap<String, String>) -> Option<String> {
+        let mut key = String::new();
+        for field in self.fields.iter() {
+            let value = values.get(field)?;
+            // Length prefixed, so that no two combinations of values share a key
+            key.push_str(&format!("{}:{}", value.len(), value));
+        }
+
+        Some(key)
+    }
+}
+
+impl fmt::Debug for IndexComposite {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("IndexComposite")
+           .field("fields", &self.fields)
+           .field("covered", &self.covered)
+           .finish()
+    }
+}
+
+fn sorted(fields: &[&str]) -> Vec<String> {
+    let mut fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+    fields.sort();
+    fields
+}
+
+fn composite_id(fields: &[String]) -> EntityId {
+    let fields: Vec<&str> = fields.iter().map(|f| f.as_str()).collect();
+    EntityId::composite_index(&fields)
+}
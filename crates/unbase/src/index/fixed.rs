@@ -1,68 +1,211 @@
 use crate::{
     context::Context,
     error::{
+        IndexShapeError,
         RetrieveError,
         WriteError,
     },
     head::Head,
     slab::{
+        EdgeLink,
         EntityId,
+        EntityType,
+        MemoRef,
         SlotId,
         MAX_SLOTS,
+        PREFETCH_TIMEOUT,
     },
 };
 
 use std::{
     collections::HashMap,
     fmt,
+    marker::PhantomData,
 };
 
 use tracing::debug;
 
-pub struct IndexFixed {
+/// A key for [`IndexFixed`]. By default each byte of the key selects the slot at one tier of the index, so the depth
+/// of the index is the length of the key, though other shapes may be chosen with [`IndexShape`]. UUIDs may be used as
+/// `[u8; 16]` or `u128`.
+pub trait FixedKey: fmt::Debug {
+    /// The length of the key in bytes
+    const LEN: usize;
+
+    /// The big-endian bytes of the key, of length LEN
+    fn key_bytes(&self) -> Vec<u8>;
+}
+
+impl FixedKey for u64 {
+    const LEN: usize = 8;
+
+    fn key_bytes(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+}
+
+impl FixedKey for u128 {
+    const LEN: usize = 16;
+
+    fn key_bytes(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+}
+
+impl<const N: usize> FixedKey for [u8; N] {
+    const LEN: usize = N;
+
+    fn key_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+/// The fan-out and depth of an [`IndexFixed`]. Each tier of the index consumes log2(fanout) bits of the key, taken
+/// from its least significant end. A shape must cover every bit of the key, as it would otherwise map several keys
+/// onto the same entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IndexShape {
+    pub fanout: usize,
+    pub depth:  u8,
+}
+
+impl IndexShape {
+    pub fn new(fanout: usize, depth: u8) -> Self {
+        assert!(fanout.is_power_of_two() && (2..=MAX_SLOTS).contains(&fanout),
+                "index fan-out must be a power of two between 2 and {}",
+                MAX_SLOTS);
+        assert!(depth > 0, "index depth must be at least one");
+
+        IndexShape { fanout, depth }
+    }
+
+    /// The default shape for a key type: one tier per byte of the key
+    pub fn for_key<K: FixedKey>() -> Self {
+        IndexShape { fanout: MAX_SLOTS,
+                     depth:  K::LEN as u8, }
+    }
+
+    /// Read the shape recorded in the values of an index root node, if any
+    pub fn from_values(values: &HashMap<String, String>) -> Option<Self> {
+        let fanout: usize = values.get("fanout")?.parse().ok()?;
+        let depth: u8 = values.get("depth")?.parse().ok()?;
+
+        if !fanout.is_power_of_two() || !(2..=MAX_SLOTS).contains(&fanout) || depth == 0 {
+            return None;
+        }
+
+        Some(IndexShape { fanout, depth })
+    }
+
+    /// Check that the shape covers every bit of the given key type
+    pub fn check<K: FixedKey>(&self) -> Result<(), IndexShapeError> {
+        let shape_bits = self.bits() * self.depth as usize;
+        let key_bits = K::LEN * 8;
+
+        if shape_bits < key_bits {
+            return Err(IndexShapeError::TooNarrow { shape_bits, key_bits });
+        }
+
+        Ok(())
+    }
+
+    /// Record the shape in the values of an index root node
+    pub fn to_values(&self, values: &mut HashMap<String, String>) {
+        values.insert("fanout".to_string(), self.fanout.to_string());
+        values.insert("depth".to_string(), self.depth.to_string());
+    }
+
+    fn bits(&self) -> usize {
+        self.fanout.trailing_zeros() as usize
+    }
+
+    /// The slot for the given key at the given tier. Bits beyond the length of the key are zero.
+    fn slot(&self, key_bytes: &[u8], tier: u8) -> SlotId {
+        let bits = self.bits();
+        let lowest = (self.depth - 1 - tier) as usize * bits;
+
+        let mut slot: SlotId = 0;
+        for bit in (lowest..lowest + bits).rev() {
+            let byte = match key_bytes.len().checked_sub(1 + bit / 8) {
+                Some(i) => key_bytes[i],
+                None => 0,
+            };
+
+            slot = slot << 1 | (byte >> (bit % 8)) & 1;
+        }
+
+        slot
+    }
+}
+
+pub struct IndexFixed<K: FixedKey = u64> {
     root:  Head,
-    depth: u8,
+    shape: IndexShape,
+    key:   PhantomData<K>,
 }
 
-impl IndexFixed {
+impl<K: FixedKey> IndexFixed<K> {
     /// Index takes everything with context, because Index is an enforcer of consistency
-    pub fn new(context: &Context, depth: u8) -> IndexFixed {
+    pub fn new(context: &Context) -> Self {
+        Self::new_with_shape(context, IndexShape::for_key::<K>()).expect("the default shape covers the key")
+    }
+
+    /// Create an index of the given shape, which must cover the key type. See [`IndexShape::check`]
+    pub fn new_with_shape(context: &Context, shape: IndexShape) -> Result<Self, IndexShapeError> {
+        shape.check::<K>()?;
+
         let mut debug_info = HashMap::new();
         debug_info.insert("tier".to_string(), "root".to_string());
+        shape.to_values(&mut debug_info);
+
+        Ok(Self { root: Head::new_index(&context.slab, debug_info),
+                  shape,
+                  key: PhantomData })
+    }
 
-        Self { root: Head::new_index(&context.slab, debug_info),
-               depth }
+    pub fn new_from_head(head: Head) -> Self {
+        Self::new_from_head_with_shape(head, IndexShape::for_key::<K>())
     }
 
-    pub fn new_from_head(depth: u8, head: Head) -> IndexFixed {
-        Self { root: head, depth }
+    pub fn new_from_head_with_shape(head: Head, shape: IndexShape) -> Self {
+        Self { root: head,
+               shape,
+               key: PhantomData }
+    }
+
+    pub fn shape(&self) -> IndexShape {
+        self.shape
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.shape.depth
+    }
+
+    pub(crate) fn root_head(&self) -> &Head {
+        &self.root
     }
 
     pub fn get_root_entity_id(&self) -> EntityId {
         self.root.entity_id().unwrap()
     }
 
-    pub async fn insert<'a>(&mut self, context: &Context, key: u64, target: Head) -> Result<(), WriteError> {
-        debug!("IndexFixed.insert({}, {:?})", key, target);
+    pub async fn insert<'a>(&mut self, context: &Context, key: K, target: Head) -> Result<(), WriteError> {
+        debug!("IndexFixed.insert({:?}, {:?})", key, target);
 
         // TODO: optimize index node creation so we're not changing relationship as an edit
         // after the fact if we don't strictly have to. That said, this gives us a great excuse
         // to work on the consistency model, so I'm doing that first.
 
+        let key_bytes = key.key_bytes();
         let mut tier = 0;
         let mut node = self.root.clone();
 
         loop {
-            // TODO: refactor this in a way that is generalizable for strings and such
-            // Could just assume we're dealing with whole bytes here, but I'd rather
-            // allow for MAX_SLOTS <> 256. Values like 128, 512, 1024 may not be entirely ridiculous
-            let exponent: u32 = (self.depth as u32 - 1) - tier as u32;
-            let x = MAX_SLOTS.pow(exponent as u32);
-            let y = ((key / (x as u64)) % MAX_SLOTS as u64) as SlotId;
+            let y = self.shape.slot(&key_bytes, tier);
 
-            // println!("Tier {}, {}, {}", tier, x, y );
+            // println!("Tier {}, {}", tier, y );
 
-            if exponent == 0 {
+            if tier == self.depth() - 1 {
                 // Leaf node
                 // println!("]]] end of the line");
 
@@ -88,7 +231,12 @@ impl IndexFixed {
                         let mut debug_info = HashMap::new();
                         debug_info.insert("tier".to_string(), tier.to_string());
 
-                        let next_node = Head::new_index(&context.slab, debug_info);
+                        // Child nodes are identified by their position in the tree, such that concurrent inserts on
+                        // different slabs converge on the same node rather than clobbering each other's edges
+                        let node_id = node.entity_id().unwrap();
+                        let next_node = Head::new_index_with_id(&context.slab,
+                                                                EntityId::index_child(node_id, y),
+                                                                debug_info);
 
                         // apply the new_node head to the context
                         // TODO POSTMERGE - determine if we can skip this apply_head because we're about to do it for
@@ -107,10 +255,40 @@ impl IndexFixed {
         }
     }
 
+    /// Vacate the entry for the given key, if there is one
+    pub async fn remove(&mut self, context: &Context, key: K) -> Result<(), WriteError> {
+        debug!("IndexFixed.remove({:?})", key);
+
+        let mut node = self.root.clone();
+
+        let key_bytes = key.key_bytes();
+
+        for tier in 0..self.depth() {
+            let y = self.shape.slot(&key_bytes, tier);
+
+            context.mut_update_index_head_for_consistency(&mut node).await?;
+
+            match node.get_edge(&context.slab, y).await? {
+                Some(Head::Null) | None => return Ok(()),
+                Some(_) if tier == self.depth() - 1 => {
+                    node.set_edge(&context.slab, y, Head::Null);
+
+                    // Apply the updated head to the context
+                    context.apply_head(&node).await?;
+
+                    return Ok(());
+                },
+                Some(n) => node = n,
+            }
+        }
+
+        panic!("Sanity error");
+    }
+
     /// Convenience method for the test suite
     #[doc(hidden)]
     #[cfg(test)]
-    pub(crate) async fn test_get_entity_handle(&self, context: &Context, key: u64)
+    pub(crate) async fn test_get_entity_handle(&self, context: &Context, key: K)
                                                -> Result<Option<crate::entity::Entity>, RetrieveError> {
         match self.get(context, key).await? {
             Some(head) => {
@@ -123,26 +301,26 @@ impl IndexFixed {
     }
 
     #[tracing::instrument]
-    pub async fn get(&self, context: &Context, key: u64) -> Result<Option<Head>, RetrieveError> {
+    pub async fn get(&self, context: &Context, key: K) -> Result<Option<Head>, RetrieveError> {
         // TODO: this is dumb, figure out how to borrow here
         //      and replace with borrows for nested entities
         let mut node = self.root.clone();
-        let max = MAX_SLOTS as u64;
+
+        let key_bytes = key.key_bytes();
 
         // let mut n;
-        for tier in 0..self.depth {
-            let exponent = (self.depth - 1) - tier;
-            let x = max.pow(exponent as u32);
-            let y = ((key / (x as u64)) % max) as SlotId;
-            debug!("Tier {}, {}, {}", tier, x, y);
+        for tier in 0..self.depth() {
+            let y = self.shape.slot(&key_bytes, tier);
+            debug!("Tier {}, {}", tier, y);
 
-            if exponent == 0 {
+            if tier == self.depth() - 1 {
                 // Leaf node
                 debug!("]]] end of the line");
 
                 context.mut_update_index_head_for_consistency(&mut node).await?;
 
-                return node.get_edge(&context.slab, y as SlotId).await;
+                // Vacated entries are recorded as an edge to Head::Null
+                return Ok(node.get_edge(&context.slab, y).await?.filter(|head| head.is_some()));
             } else {
                 // branch
 
@@ -158,6 +336,131 @@ impl IndexFixed {
         panic!("Sanity error");
     }
 
+    /// Look up several keys at once. The index is walked a tier at a time, such that each node is visited only once
+    /// however many of the keys lie beneath it, and the nodes of each tier are fetched together.
+    pub async fn get_many(&self, context: &Context, keys: &[K]) -> Result<Vec<Option<Head>>, RetrieveError> {
+        let key_bytes: Vec<Vec<u8>> = keys.iter().map(|key| key.key_bytes()).collect();
+        let mut found = vec![None; keys.len()];
+        if keys.is_empty() {
+            return Ok(found);
+        }
+
+        // Each node of the present tier, along with the keys which lie beneath it
+        let mut frontier: Vec<(Head, Vec<usize>)> = vec![(self.root.clone(), (0..keys.len()).collect())];
+
+        for tier in 0..self.depth() {
+            for (node, _) in frontier.iter_mut() {
+                context.mut_update_index_head_for_consistency(node).await?;
+            }
+
+            let memorefs: Vec<MemoRef> = frontier.iter().flat_map(|(node, _)| node.iter().cloned()).collect();
+            context.slab.fetch_memos(&memorefs, PREFETCH_TIMEOUT).await;
+
+            let mut next = Vec::new();
+            for (mut node, indices) in frontier {
+                let mut slots: HashMap<SlotId, Vec<usize>> = HashMap::new();
+                for i in indices {
+                    slots.entry(self.shape.slot(&key_bytes[i], tier)).or_default().push(i);
+                }
+
+                for (slot_id, indices) in slots {
+                    // Vacated entries are recorded as an edge to Head::Null
+                    let child = match node.get_edge(&context.slab, slot_id).await? {
+                        Some(child) if child.is_some() => child,
+                        _ => continue,
+                    };
+
+                    if tier == self.depth() - 1 {
+                        for i in indices {
+                            found[i] = Some(child.clone());
+                        }
+                    } else {
+                        next.push((child, indices));
+                    }
+                }
+            }
+
+            frontier = next;
+        }
+
+        Ok(found)
+    }
+
+    /// Collect the heads of all entries in the index
+    pub async fn entries(&self, context: &Context) -> Result<Vec<Head>, RetrieveError> {
+        let mut entries = Vec::new();
+        let mut stack: Vec<(Head, u8)> = vec![(self.root.clone(), 0)];
+
+        while let Some((mut node, tier)) = stack.pop() {
+            context.mut_update_index_head_for_consistency(&mut node).await?;
+
+            for edgelink in node.project_occupied_edges(&context.slab).await? {
+                if let EdgeLink::Occupied { head, .. } = edgelink {
+                    if tier == self.depth() - 1 {
+                        entries.push(head);
+                    } else {
+                        stack.push((head, tier + 1));
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Collect the heads of up to `limit` entries, in key order, the keys of which are greater than `after`.
+    /// Only the branches of the tree which may contain such keys are visited.
+    pub async fn entries_after(&self, context: &Context, after: Option<K>, limit: usize)
+                               -> Result<Vec<Head>, RetrieveError> {
+        let after = after.map(|key| key.key_bytes());
+        let mut entries = Vec::new();
+        if limit == 0 {
+            return Ok(entries);
+        }
+
+        // Nodes which lie on the path to `after` are bounded; their siblings to the right are not
+        let mut stack: Vec<(Head, u8, bool)> = vec![(self.root.clone(), 0, after.is_some())];
+
+        while let Some((mut node, tier, bounded)) = stack.pop() {
+            context.mut_update_index_head_for_consistency(&mut node).await?;
+
+            let mut edgelinks = node.project_occupied_edges(&context.slab).await?;
+            edgelinks.sort_by_key(|edgelink| edgelink.slot_id());
+
+            let leaf = tier == self.depth() - 1;
+            let mut children = Vec::new();
+
+            for edgelink in edgelinks {
+                if let EdgeLink::Occupied { slot_id, head } = edgelink {
+                    let child_bounded = match after {
+                        Some(ref key_bytes) if bounded => {
+                            let bound = self.shape.slot(key_bytes, tier);
+                            if slot_id < bound || (leaf && slot_id == bound) {
+                                continue;
+                            }
+                            slot_id == bound
+                        },
+                        _ => false,
+                    };
+
+                    if leaf {
+                        entries.push(head);
+                        if entries.len() == limit {
+                            return Ok(entries);
+                        }
+                    } else {
+                        children.push((head, tier + 1, child_bounded));
+                    }
+                }
+            }
+
+            // Visit the lowest slot first
+            stack.extend(children.into_iter().rev());
+        }
+
+        Ok(entries)
+    }
+
     pub async fn scan_first_kv(&mut self, context: &Context, key: &str, value: &str) -> Result<Option<Head>, RetrieveError> {
         // TODO POSTMERGE - figure out how the hell to make this work with a closure
         //
@@ -191,12 +494,12 @@ impl IndexFixed {
         let mut stack: Vec<(Head, usize)> = vec![(self.root.clone(), 0)];
 
         while let Some((mut node, tier)) = stack.pop() {
-            if tier as u8 == self.depth - 1 {
+            if tier as u8 == self.depth() - 1 {
                 // TODO NEXT / WIP: finish converting this to stack based recursion.
                 // Seems the compiler doesn't like something here. Most likely has to do with
 
-                // println!("LAST Non-leaf node   {}, {}, {}", node.id, tier, self.depth );
-                for slot_id in 0..MAX_SLOTS {
+                // println!("LAST Non-leaf node   {}, {}, {}", node.id, tier, self.depth() );
+                for slot_id in 0..self.shape.fanout {
                     context.mut_update_index_head_for_consistency(&mut node).await?;
                     if let Some(mut head) = node.get_edge(&context.slab, slot_id as SlotId).await? {
                         //                        TODO POSTMERGE - update this to take a closure
@@ -206,6 +509,13 @@ impl IndexFixed {
 
                         context.mut_update_index_head_for_consistency(&mut node).await?;
 
+                        // Index roots and schemas are listed too, but their values are not those of any record
+                        match head.entity_id() {
+                            Some(EntityId { stype: EntityType::Record, .. })
+                            | Some(EntityId { stype: EntityType::Custom(_), .. }) => {},
+                            _ => continue,
+                        }
+
                         if let Some(v) = head.get_value(&context.slab, key).await? {
                             if v == value {
                                 return Ok(Some(head));
@@ -214,8 +524,8 @@ impl IndexFixed {
                     }
                 }
             } else {
-                // println!("RECURSE {}, {}, {}", node.id, tier, self.depth );
-                for slot_id in 0..MAX_SLOTS {
+                // println!("RECURSE {}, {}, {}", node.id, tier, self.depth() );
+                for slot_id in 0..self.shape.fanout {
                     context.mut_update_index_head_for_consistency(&mut node).await?;
                     if let Some(child) = node.get_edge(&context.slab, slot_id as SlotId).await? {
                         stack.push((child, tier + 1))
@@ -228,7 +538,7 @@ impl IndexFixed {
     }
 }
 
-impl fmt::Debug for IndexFixed {
+impl<K: FixedKey> fmt::Debug for IndexFixed<K> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("IndexFixed").finish()
     }
@@ -237,13 +547,25 @@ impl fmt::Debug for IndexFixed {
 #[cfg(test)]
 mod test {
     use crate::{
-        index::IndexFixed,
+        context::Context,
+        error::IndexShapeError,
+        head::Head,
+        index::{
+            IndexFixed,
+            IndexShape,
+        },
+        slab::{
+            EdgeLink,
+            EntityId,
+        },
         util::simulator::Simulator,
         Entity,
         Network,
         Slab,
     };
 
+    use std::collections::HashMap;
+
     #[unbase_test_util::async_test]
     async fn index_construction() {
         let net = Network::create_new_system();
@@ -252,7 +574,7 @@ mod test {
         let slab_a = Slab::new(&net);
         let context_a = slab_a.create_context();
 
-        let mut index = IndexFixed::new(&context_a, 5);
+        let mut index: IndexFixed = IndexFixed::new(&context_a);
 
         assert_eq!(context_a.is_fully_materialized().await.unwrap(), true);
 
@@ -302,4 +624,149 @@ mod test {
                    "275",
                    "Is correct record");
     }
+
+    #[unbase_test_util::async_test]
+    async fn index_byte_string_keys() {
+        let net = Network::create_new_system();
+        let slab_a = Slab::new(&net);
+        let context_a = slab_a.create_context();
+
+        // Depth is derived from the key length
+        let mut short: IndexFixed<[u8; 3]> = IndexFixed::new(&context_a);
+        let mut wide: IndexFixed<u128> = IndexFixed::new(&context_a);
+        assert_eq!(short.depth(), 3);
+        assert_eq!(wide.depth(), 16);
+
+        let tiger = Entity::new_with_single_kv(&context_a, "name", "Tiger").await.unwrap();
+        let cow = Entity::new_with_single_kv(&context_a, "name", "Cow").await.unwrap();
+
+        short.insert(&context_a, *b"tgr", tiger.head.clone()).await.unwrap();
+        short.insert(&context_a, *b"cow", cow.head.clone()).await.unwrap();
+
+        let uuid = 0x6ba7b810_9dad_11d1_80b4_00c04fd430c8u128;
+        wide.insert(&context_a, uuid, tiger.head.clone()).await.unwrap();
+
+        let mut found = short.test_get_entity_handle(&context_a, *b"cow").await.unwrap().expect("found");
+        assert_eq!(found.get_value("name").await.unwrap(), Some("Cow".to_string()));
+        assert!(short.get(&context_a, *b"cat").await.unwrap().is_none());
+
+        let found = wide.test_get_entity_handle(&context_a, uuid).await.unwrap().expect("found");
+        assert_eq!(found.id, tiger.id);
+        assert!(wide.get(&context_a, uuid + 1).await.unwrap().is_none());
+
+        short.remove(&context_a, *b"cow").await.unwrap();
+        assert!(short.get(&context_a, *b"cow").await.unwrap().is_none());
+        assert_eq!(short.entries(&context_a).await.unwrap().len(), 1);
+    }
+
+    /// Set the edge of a node to a child, each of which is the same entity on every slab, and write to the child
+    async fn write_node(context: &Context, node_id: EntityId, child_id: EntityId, field: &str) -> Head {
+        let mut child = Head::new_index_with_id(&context.slab, child_id, HashMap::new());
+        child.set_value(&context.slab, field, "1").await.unwrap();
+        context.apply_head(&child).await.unwrap();
+
+        let mut node = Head::new_index_with_id(&context.slab, node_id, HashMap::new());
+        node.set_edge(&context.slab, 7, child);
+        context.apply_head(&node).await.unwrap()
+    }
+
+    #[unbase_test_util::async_test]
+    async fn index_concurrent_edges() {
+        let net = Network::create_new_system();
+        let simulator = Simulator::new();
+        net.add_transport(Box::new(simulator.clone()));
+        let slab_a = Slab::new(&net);
+        let slab_b = Slab::new(&net);
+        let context_a = slab_a.create_context();
+        let context_b = slab_b.create_context();
+
+        let node_id = EntityId::type_index(1);
+        let child_id = EntityId::index_child(node_id, 7);
+
+        let mut node = write_node(&context_a, node_id, child_id, "a").await;
+        write_node(&context_b, node_id, child_id, "b").await;
+
+        simulator.start();
+        simulator.quiesce().await;
+        context_b.hack_send_context(&context_a).await.unwrap();
+
+        // Both slabs set the edge concurrently, so each branch leads to a different head of the child
+        context_a.mut_update_index_head_for_consistency(&mut node).await.unwrap();
+        assert_eq!(node.len(), 2);
+
+        let mut edges = node.project_occupied_edges(&context_a.slab).await.unwrap();
+        assert_eq!(edges.len(), 1);
+
+        let projected = match edges.remove(0) {
+            EdgeLink::Occupied { slot_id: 7, head } => head,
+            other => panic!("unexpected {:?}", other),
+        };
+        let got = node.get_edge(&context_a.slab, 7).await.unwrap().expect("edge");
+
+        // Which are merged, such that neither slab's write to the child goes missing
+        for child in [projected, got].iter() {
+            let values = child.project_values(&context_a.slab).await.unwrap();
+            assert_eq!(values.get("a").map(|v| v.as_str()), Some("1"));
+            assert_eq!(values.get("b").map(|v| v.as_str()), Some("1"));
+        }
+
+        simulator.quiesce_and_stop().await;
+    }
+
+    #[test]
+    fn index_shape_slots() {
+        let key = 0xABCDu64.to_be_bytes();
+
+        let default = IndexShape::for_key::<u64>();
+        assert_eq!(default.slot(&key, 6), 0xAB);
+        assert_eq!(default.slot(&key, 7), 0xCD);
+
+        let narrow = IndexShape::new(16, 4);
+        let slots: Vec<_> = (0..4).map(|tier| narrow.slot(&key, tier)).collect();
+        assert_eq!(slots, vec![0xA, 0xB, 0xC, 0xD]);
+
+        // Tiers beyond the length of the key are zero
+        let deep = IndexShape::new(256, 10);
+        assert_eq!(deep.slot(&key, 0), 0);
+        assert_eq!(deep.slot(&key, 9), 0xCD);
+    }
+
+    #[unbase_test_util::async_test]
+    async fn index_narrow_fanout() {
+        let net = Network::create_new_system();
+        let slab_a = Slab::new(&net);
+        let context_a = slab_a.create_context();
+
+        // A shape which covers only the low twelve bits of a u64 would alias keys such as 7 and 4096 + 7
+        assert_eq!(IndexFixed::<u64>::new_with_shape(&context_a, IndexShape::new(4, 6)).unwrap_err(),
+                   IndexShapeError::TooNarrow { shape_bits: 12,
+                                                key_bits:   64, });
+
+        let mut index: IndexFixed<[u8; 2]> = IndexFixed::new_with_shape(&context_a, IndexShape::new(4, 8)).unwrap();
+
+        let mut records = Vec::new();
+        for i in 0..64u16 {
+            let record = Entity::new_with_single_kv(&context_a, "record number", &i.to_string()).await.unwrap();
+            index.insert(&context_a, (i * 1000).to_be_bytes(), record.head.clone()).await.unwrap();
+            records.push(record);
+        }
+
+        for (i, record) in records.iter().enumerate() {
+            let key = (i as u16 * 1000).to_be_bytes();
+            let found = index.test_get_entity_handle(&context_a, key).await.unwrap().expect("found");
+            assert_eq!(found.id, record.id);
+        }
+        assert_eq!(index.entries(&context_a).await.unwrap().len(), 64);
+        assert!(index.get(&context_a, 7u16.to_be_bytes()).await.unwrap().is_none());
+
+        // Several keys may be looked up in a single walk, including absent ones
+        let keys = [5000u16.to_be_bytes(), 7u16.to_be_bytes(), 0u16.to_be_bytes(), 5000u16.to_be_bytes()];
+        let found: Vec<_> = index.get_many(&context_a, &keys)
+                                 .await
+                                 .unwrap()
+                                 .into_iter()
+                                 .map(|head| head.and_then(|head| head.entity_id()))
+                                 .collect();
+        assert_eq!(found, vec![Some(records[5].id), None, Some(records[0].id), Some(records[5].id)]);
+    }
 }
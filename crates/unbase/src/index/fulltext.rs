@@ -0,0 +1,227 @@
+use crate::{
+    context::Context,
+    entity::Entity,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    index::{
+        Declared,
+        IndexKind,
+        IndexSecondary,
+    },
+    slab::EntityId,
+};
+
+use std::{
+    collections::{
+        BTreeSet,
+        HashMap,
+    },
+    fmt,
+};
+
+use tracing::debug;
+
+/// A full-text index maps the words found in the values of a given field to the entities whose values contain them.
+///
+/// Values are split into terms by [`tokenize`], and each term is listed in a posting just as a value is in an
+/// [`IndexSecondary`], so the index replicates like any other. Whether terms are stemmed is recorded on the root node,
+/// so that every context which opens the index tokenizes alike.
+pub struct IndexFullText {
+    stemming: bool,
+    postings: IndexSecondary,
+}
+
+/// An entity found by [`Context::search`], with its relevance to the search terms
+#[derive(Debug)]
+pub struct SearchHit {
+    pub entity: Entity,
+    pub score:  f64,
+}
+
+impl IndexFullText {
+    /// Create the full-text index for a field, or open it if it already exists. The `stemming` of an existing index is
+    /// left as it was.
+    pub async fn declare(context: &Context, field: &str, stemming: bool) -> Result<IndexFullText, WriteError> {
+        if let Some(index) = Self::open(context, field).await? {
+            return Ok(index);
+        }
+
+        let mut values = HashMap::new();
+        values.insert("stemming".to_string(), stemming.to_string());
+
+        let postings = IndexSecondary::declare_with_id(context, field, EntityId::fulltext_index(field), values).await?;
+
+        let declared = Declared { kind:    IndexKind::FullText,
+                                  fields:  vec![field.to_string()],
+                                  covered: Vec::new(), };
+        declared.record(context).await?;
+
+        Ok(IndexFullText { stemming, postings })
+    }
+
+    /// Open the full-text index for a field, if one has been declared
+    pub async fn open(context: &Context, field: &str) -> Result<Option<IndexFullText>, RetrieveError> {
+        let postings = match IndexSecondary::open_with_id(context, field, EntityId::fulltext_index(field)).await? {
+            Some(postings) => postings,
+            None => return Ok(None),
+        };
+
+        let values = postings.root_head().project_values(&context.slab).await?;
+        let stemming = values.get("stemming").is_some_and(|s| s == "true");
+
+        Ok(Some(IndexFullText { stemming, postings }))
+    }
+
+    pub fn field(&self) -> &str {
+        self.postings.field()
+    }
+
+    /// The distinct terms of the given text, as this index would record them
+    pub fn terms(&self, text: &str) -> BTreeSet<String> {
+        tokenize(text, self.stemming).into_iter().collect()
+    }
+
+    /// Record the terms of an entity's new value, and forget those of its old value which no longer apply
+    pub async fn update(&mut self, context: &Context, old: Option<&str>, new: &str, head: Head)
+                        -> Result<(), WriteError> {
+        let entity_id = head.entity_id().ok_or(WriteError::BadTarget)?;
+
+        let old_terms = old.map(|old| self.terms(old)).unwrap_or_default();
+        let new_terms = self.terms(new);
+        debug!("IndexFullText({}).update({}, -{:?}, +{:?})", self.field(), entity_id, old_terms, new_terms);
+
+        for term in old_terms.difference(&new_terms) {
+            self.postings.remove(context, term, entity_id).await?;
+        }
+        for term in new_terms.difference(&old_terms) {
+            self.postings.insert(context, term, head.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve the heads of all entities listed under the given term. As with [`IndexSecondary::get`], entries may
+    /// be stale, so callers must check the value of the entity itself.
+    pub async fn get(&self, context: &Context, term: &str) -> Result<Vec<Head>, RetrieveError> {
+        self.postings.get(context, term).await
+    }
+
+    /// Find the entities whose values contain any of the terms of the given text, most relevant first. Relevance is
+    /// the number of occurrences of each term, weighted by the rarity of the term among the matching entities.
+    pub async fn search(&self, context: &Context, text: &str) -> Result<Vec<SearchHit>, RetrieveError> {
+        let terms = self.terms(text);
+
+        let mut frequency: HashMap<&str, usize> = HashMap::new();
+        let mut candidates: HashMap<EntityId, Head> = HashMap::new();
+
+        for term in terms.iter() {
+            let heads = self.get(context, term).await?;
+            frequency.insert(term, heads.len());
+
+            for head in heads {
+                if let Some(entity_id) = head.entity_id() {
+                    candidates.entry(entity_id).or_insert(head);
+                }
+            }
+        }
+
+        let total = candidates.len() as f64;
+        let mut hits = Vec::new();
+
+        for (_, head) in candidates {
+            let mut entity = context.get_entity_from_head(head).await?;
+
+            let value = match entity.get_value(self.field()).await? {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let mut score = 0.0;
+            for term in tokenize(&value, self.stemming) {
+                if let Some(&frequency) = frequency.get(term.as_str()) {
+                    score += (1.0 + total / frequency as f64).ln();
+                }
+            }
+
+            if score > 0.0 {
+                hits.push(SearchHit { entity, score });
+            }
+        }
+
+        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap().then(a.entity.id.cmp(&b.entity.id)));
+
+        Ok(hits)
+    }
+}
+
+impl fmt::Debug for IndexFullText {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("IndexFullText")
+           .field("field", &self.field())
+           .field("stemming", &self.stemming)
+           .finish()
+    }
+}
+
+/// Split text into lowercase terms at anything other than letters and digits, optionally stemming each
+pub fn tokenize(text: &str, stemming: bool) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = word.to_lowercase();
+            if stemming {
+                stem(&word)
+            } else {
+                word
+            }
+        })
+        .collect()
+}
+
+/// A deliberately light stemmer for English, which strips common inflections such that "tigers" and "tiger" or
+/// "striped" and "stripes" are one and the same term. Short words are left alone.
+fn stem(word: &str) -> String {
+    if word.chars().count() <= 3 {
+        return word.to_string();
+    }
+
+    let stem = if let Some(stem) = word.strip_suffix("sses") {
+        format!("{}ss", stem)
+    } else if let Some(stem) = word.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if let Some(stem) = ["ing", "ed"].iter()
+                                           .filter_map(|suffix| word.strip_suffix(suffix))
+                                           .find(|stem| stem.chars().count() >= 3)
+    {
+        stem.to_string()
+    } else {
+        match word.strip_suffix('s') {
+            Some(stem) if !stem.ends_with('s') => stem.to_string(),
+            _ => word.to_string(),
+        }
+    };
+
+    // A silent e is dropped, as it would have been before -ed or -ing
+    match stem.strip_suffix('e') {
+        Some(without) if without.chars().count() >= 3 => without.to_string(),
+        _ => stem,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fulltext_tokenize() {
+        assert_eq!(tokenize("The quick, brown FOX!", false), vec!["the", "quick", "brown", "fox"]);
+        assert_eq!(tokenize("Tigers roaring; roared at the pony's ponies", true),
+                   vec!["tiger", "roar", "roar", "at", "the", "pony", "s", "pony"]);
+        assert_eq!(tokenize("grass glass is bus", true), vec!["grass", "glass", "is", "bus"]);
+        assert_eq!(tokenize("stripe stripes striped striping", true), vec!["strip", "strip", "strip", "strip"]);
+        assert!(tokenize(" -- ", true).is_empty());
+    }
+}
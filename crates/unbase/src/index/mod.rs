@@ -1,5 +1,37 @@
+mod catalog;
+mod composite;
 mod fixed;
-pub use self::fixed::IndexFixed;
+mod fulltext;
+mod ordered;
+mod secondary;
+mod typed;
+pub use self::{
+    composite::{
+        Covered,
+        IndexComposite,
+    },
+    fixed::{
+        FixedKey,
+        IndexFixed,
+        IndexShape,
+    },
+    fulltext::{
+        tokenize,
+        IndexFullText,
+        SearchHit,
+    },
+    ordered::{
+        IndexOrdered,
+        OrderedKey,
+    },
+    secondary::IndexSecondary,
+    typed::IndexTyped,
+};
+pub(crate) use self::catalog::{
+    read_catalog,
+    Declared,
+    IndexKind,
+};
 use crate::head::Head;
 
 trait Index {
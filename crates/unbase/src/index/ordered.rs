@@ -0,0 +1,722 @@
+use crate::{
+    context::Context,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    index::{
+        Declared,
+        IndexKind,
+    },
+    query::Comparison,
+    slab::{
+        EdgeLink,
+        EdgeSet,
+        EntityId,
+        EntityType,
+        MemoBody,
+        RelationSet,
+        SlotId,
+    },
+};
+
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{
+        Bound,
+        RangeBounds,
+    },
+};
+
+use tracing::debug;
+
+/// The maximum number of entries in a node. Nodes are split in half when they reach this size
+const ORDER: usize = 64;
+
+/// Reserved key denoting whether a node is a leaf ("1") or a branch ("0")
+const LEAF_KEY: &str = "leaf";
+
+/// Reserved key of the root node, naming the field which the index is declared on
+const FIELD_KEY: &str = "field";
+
+/// Entity ids are appended to encoded keys as fixed-width hex, such that several entities may share a key
+const ENTITY_ID_HEX_LEN: usize = 16;
+
+/// A key for [`IndexOrdered`]. Keys of different types do not compare meaningfully with each other, but are ordered
+/// Int < Float < Str nonetheless.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrderedKey {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl OrderedKey {
+    /// The key under which an ordered index lists a value of its field. Values are compared numerically when they are
+    /// numbers, so they are listed as such.
+    pub fn for_value(value: &str) -> OrderedKey {
+        match value.parse::<f64>() {
+            Ok(f) => OrderedKey::Float(f),
+            Err(_) => OrderedKey::Str(value.to_string()),
+        }
+    }
+
+    /// Encode the key as a hex string, the lexical order of which is the order of the keys
+    fn encode(&self) -> String {
+        let mut bytes = Vec::new();
+
+        match self {
+            OrderedKey::Int(i) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&(*i as u64 ^ 1 << 63).to_be_bytes());
+            },
+            OrderedKey::Float(f) => {
+                let bits = f.to_bits();
+                let bits = if bits >> 63 == 1 { !bits } else { bits ^ 1 << 63 };
+
+                bytes.push(2);
+                bytes.extend_from_slice(&bits.to_be_bytes());
+            },
+            OrderedKey::Str(s) => {
+                bytes.push(3);
+                // Escape nulls, so that the terminator sorts shorter strings before their extensions
+                for byte in s.bytes() {
+                    bytes.push(byte);
+                    if byte == 0 {
+                        bytes.push(0xFF);
+                    }
+                }
+                bytes.extend_from_slice(&[0, 1]);
+            },
+        }
+
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn decode(encoded: &str) -> Option<OrderedKey> {
+        let bytes = (0..encoded.len()).step_by(2)
+                                      .map(|i| u8::from_str_radix(encoded.get(i..i + 2)?, 16).ok())
+                                      .collect::<Option<Vec<u8>>>()?;
+
+        let (tag, rest) = bytes.split_first()?;
+        match tag {
+            1 => Some(OrderedKey::Int((u64::from_be_bytes(fixed(rest)?) ^ 1 << 63) as i64)),
+            2 => {
+                let bits = u64::from_be_bytes(fixed(rest)?);
+                let bits = if bits >> 63 == 1 { bits ^ 1 << 63 } else { !bits };
+
+                Some(OrderedKey::Float(f64::from_bits(bits)))
+            },
+            3 => {
+                let mut string = Vec::new();
+                let mut i = 0;
+                loop {
+                    match (rest.get(i)?, rest.get(i + 1)) {
+                        (0, Some(0xFF)) => {
+                            string.push(0);
+                            i += 2;
+                        },
+                        (0, _) => break,
+                        (byte, _) => {
+                            string.push(*byte);
+                            i += 1;
+                        },
+                    }
+                }
+
+                String::from_utf8(string).ok().map(OrderedKey::Str)
+            },
+            _ => None,
+        }
+    }
+}
+
+fn fixed(bytes: &[u8]) -> Option<[u8; 8]> {
+    let mut out = [0u8; 8];
+    if bytes.len() != 8 {
+        return None;
+    }
+    out.copy_from_slice(bytes);
+    Some(out)
+}
+
+impl From<i64> for OrderedKey {
+    fn from(i: i64) -> Self {
+        OrderedKey::Int(i)
+    }
+}
+
+impl From<f64> for OrderedKey {
+    fn from(f: f64) -> Self {
+        OrderedKey::Float(f)
+    }
+}
+
+impl From<&str> for OrderedKey {
+    fn from(s: &str) -> Self {
+        OrderedKey::Str(s.to_string())
+    }
+}
+
+impl From<String> for OrderedKey {
+    fn from(s: String) -> Self {
+        OrderedKey::Str(s)
+    }
+}
+
+/// An ordered index supporting range and reverse scans, laid out as a B+tree of index nodes.
+///
+/// Each node lists up to ORDER entries, under values named for the slot of the edge they correspond to. Entries are not
+/// kept in order within a node, such that an insert writes only to the affected leaf, rather than rewriting its
+/// siblings. Parent edges are not updated when a child node changes, as the context stash ensures that the latest head
+/// of each node is considered. Nodes are split preemptively on the way down, and splitting the root keeps its id
+/// stable. Emptied nodes are not presently merged.
+///
+/// An index declared on a field via [`Context::declare_ordered_index`] has a root node with a deterministic id, which
+/// is stored in the root index and listed in the index catalog, just like an [`IndexSecondary`]. It is maintained as
+/// the field is written, and consulted by queries with a comparison predicate on the field.
+///
+/// Concurrent inserts on different slabs are merged, but concurrent splits of the same node are not yet reconciled.
+///
+/// [`IndexSecondary`]: crate::index::IndexSecondary
+pub struct IndexOrdered {
+    root: Head,
+}
+
+struct Node {
+    head:    Head,
+    leaf:    bool,
+    /// (encoded key with entity id suffix, slot), sorted by the former
+    entries: Vec<(String, SlotId)>,
+    /// Values of the node other than its entries, such as the field of a root, which are kept when it is rewritten
+    other:   HashMap<String, String>,
+}
+
+impl IndexOrdered {
+    pub fn new(context: &Context) -> IndexOrdered {
+        let mut values = HashMap::new();
+        values.insert(LEAF_KEY.to_string(), "1".to_string());
+
+        Self { root: Head::new_index(&context.slab, values), }
+    }
+
+    pub fn new_from_head(head: Head) -> IndexOrdered {
+        Self { root: head }
+    }
+
+    /// Create the ordered index for a field, or open it if it already exists
+    pub async fn declare(context: &Context, field: &str) -> Result<IndexOrdered, WriteError> {
+        if let Some(index) = Self::open(context, field).await? {
+            return Ok(index);
+        }
+
+        let entity_id = EntityId::ordered_index(field);
+
+        let mut values = HashMap::new();
+        values.insert(LEAF_KEY.to_string(), "1".to_string());
+        values.insert(FIELD_KEY.to_string(), field.to_string());
+
+        let root = Head::new_index_with_id(&context.slab, entity_id, values);
+        context.apply_head(&root).await?;
+        context.update_indices(entity_id, &root).await?;
+
+        let declared = Declared { kind:    IndexKind::Ordered,
+                                  fields:  vec![field.to_string()],
+                                  covered: Vec::new(), };
+        declared.record(context).await?;
+
+        Ok(IndexOrdered { root })
+    }
+
+    /// Open the ordered index for a field, if one has been declared
+    pub async fn open(context: &Context, field: &str) -> Result<Option<IndexOrdered>, RetrieveError> {
+        let entity_id = EntityId::ordered_index(field);
+
+        match context.root_index().await?.get(context, entity_id.id).await? {
+            // The root index is keyed by id alone, so make sure we didn't find some other entity
+            Some(head) if head.entity_id() == Some(entity_id) => Ok(Some(IndexOrdered { root: head })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Move an entity from the key of its old value, if any, to that of its new value
+    pub async fn update(&mut self, context: &Context, old: Option<&str>, new: &str, head: Head) -> Result<(), WriteError> {
+        let entity_id = head.entity_id().ok_or(WriteError::BadTarget)?;
+
+        if let Some(old) = old {
+            self.remove(context, &OrderedKey::for_value(old), entity_id).await?;
+        }
+
+        self.insert(context, OrderedKey::for_value(new), head).await
+    }
+
+    /// The heads of the entities whose values may satisfy the given comparison with `value`, as compared by a query.
+    /// Numbers are compared numerically with other numbers, but lexically with anything else, so the entities listed
+    /// under keys of the other kind are drawn from the lexical order of their values, or included outright.
+    pub(crate) async fn candidates(&self, context: &Context, comparison: Comparison, value: &str)
+                                   -> Result<Vec<Head>, RetrieveError> {
+        let bound = OrderedKey::for_value(value);
+        let text = OrderedKey::Str(value.to_string());
+
+        // Numbers are listed before strings, the least of which is the empty string
+        let numbers = (Bound::Unbounded, Bound::Excluded(OrderedKey::from("")));
+        let strings = (Bound::Included(OrderedKey::from("")), Bound::Unbounded);
+
+        let within = |(lower, upper): (Bound<OrderedKey>, Bound<OrderedKey>), key: &OrderedKey| {
+            match comparison {
+                Comparison::Lt => (lower, Bound::Excluded(key.clone())),
+                Comparison::Le => (lower, Bound::Included(key.clone())),
+                Comparison::Gt => (Bound::Excluded(key.clone()), upper),
+                Comparison::Ge => (Bound::Included(key.clone()), upper),
+                Comparison::Ne => (lower, upper),
+            }
+        };
+
+        let ranges = match bound {
+            OrderedKey::Str(_) => vec![numbers, within(strings, &text)],
+            _ => vec![within(numbers, &bound), within(strings, &text)],
+        };
+
+        let mut heads = Vec::new();
+        for range in ranges {
+            heads.extend(self.range(context, range).await?.into_iter().map(|(_, head)| head));
+        }
+
+        Ok(heads)
+    }
+
+    pub fn get_root_entity_id(&self) -> EntityId {
+        self.root.entity_id().unwrap()
+    }
+
+    /// Insert the target under the given key. Several entities may share a key, but inserting the same entity under the
+    /// same key again replaces the previous entry
+    pub async fn insert(&mut self, context: &Context, key: OrderedKey, target: Head) -> Result<(), WriteError> {
+        let entity_id = target.entity_id().ok_or(WriteError::BadTarget)?;
+        let composite = composite_key(&key, entity_id);
+        debug!("IndexOrdered.insert({:?}, {})", key, entity_id);
+
+        let mut node = Node::load(context, self.root.clone()).await?;
+        if node.entries.len() >= ORDER {
+            node = self.split_root(context, node).await?;
+        }
+
+        loop {
+            if node.leaf {
+                let slot_id = match node.entries.iter().find(|(c, _)| *c == composite) {
+                    Some((_, slot_id)) => *slot_id,
+                    None => node.free_slot(),
+                };
+
+                return node.set_entry(context, slot_id, composite, target).await;
+            }
+
+            let slot_id = node.child_for(&composite);
+            let mut child = Node::load(context, node.edge(context, slot_id).await?).await?;
+
+            if child.entries.len() >= ORDER {
+                let (separator, sibling) = child.split(context).await?;
+                let sibling_slot_id = node.free_slot();
+                node.set_entry(context, sibling_slot_id, separator.clone(), sibling.head.clone())
+                    .await?;
+
+                if composite >= separator {
+                    child = sibling;
+                }
+            }
+
+            node = child;
+        }
+    }
+
+    /// Remove the entry for the given entity under the given key, if there is one
+    pub async fn remove(&mut self, context: &Context, key: &OrderedKey, entity_id: EntityId) -> Result<(), WriteError> {
+        let composite = composite_key(key, entity_id);
+        debug!("IndexOrdered.remove({:?}, {})", key, entity_id);
+
+        let mut node = Node::load(context, self.root.clone()).await?;
+        while !node.leaf {
+            let slot_id = node.child_for(&composite);
+            node = Node::load(context, node.edge(context, slot_id).await?).await?;
+        }
+
+        if let Some((_, slot_id)) = node.entries.iter().find(|(c, _)| *c == composite).cloned() {
+            let mut values = HashMap::new();
+            values.insert(slot_key(slot_id), String::new());
+
+            node.head.set_values(&context.slab, values).await?;
+            node.head.set_edge(&context.slab, slot_id, Head::Null);
+            context.apply_head(&node.head).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve all entries with keys in the given range, in ascending order
+    pub async fn range<R>(&self, context: &Context, range: R) -> Result<Vec<(OrderedKey, Head)>, RetrieveError>
+        where R: RangeBounds<OrderedKey>
+    {
+        let lower = encode_bound(range.start_bound());
+        let upper = encode_bound(range.end_bound());
+
+        let mut out = Vec::new();
+        let mut stack = vec![self.root.clone()];
+
+        while let Some(head) = stack.pop() {
+            let node = Node::load(context, head).await?;
+            let mut edges = node.edges(context).await?;
+
+            if node.leaf {
+                for (composite, slot_id) in node.entries.iter() {
+                    let encoded = key_part(composite);
+                    if above(encoded, &lower) && below(encoded, &upper) {
+                        if let (Some(key), Some(head)) = (OrderedKey::decode(encoded), edges.remove(slot_id)) {
+                            out.push((key, head));
+                        }
+                    }
+                }
+            } else {
+                // Child i holds keys between separators i and i+1. Push in reverse, so that children are visited in order
+                for (i, (composite, slot_id)) in node.entries.iter().enumerate().rev() {
+                    let overlaps_upper = i == 0 || below(key_part(composite), &upper);
+                    let overlaps_lower = match node.entries.get(i + 1) {
+                        Some((next, _)) => above(key_part(next), &lower),
+                        None => true,
+                    };
+
+                    if overlaps_upper && overlaps_lower {
+                        if let Some(child) = edges.remove(slot_id) {
+                            stack.push(child);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Retrieve all entries with keys in the given range, in descending order
+    pub async fn range_rev<R>(&self, context: &Context, range: R) -> Result<Vec<(OrderedKey, Head)>, RetrieveError>
+        where R: RangeBounds<OrderedKey>
+    {
+        let mut out = self.range(context, range).await?;
+        out.reverse();
+
+        Ok(out)
+    }
+
+    /// Move the contents of the root into two new children, such that the root retains its id
+    async fn split_root(&mut self, context: &Context, root: Node) -> Result<Node, WriteError> {
+        let edges = root.edges(context).await?;
+        let mid = root.entries.len() / 2;
+
+        let lower = Node::create(context, root.leaf, &root.entries[..mid], &edges).await?;
+        let upper = Node::create(context, root.leaf, &root.entries[mid..], &edges).await?;
+
+        let entries = vec![(root.entries[0].0.clone(), 0), (root.entries[mid].0.clone(), 1)];
+        let mut children = HashMap::new();
+        children.insert(0, lower.head);
+        children.insert(1, upper.head);
+
+        let root = root.keyframe(context, false, entries, &children).await?;
+        self.root = root.head.clone();
+
+        Ok(root)
+    }
+}
+
+impl Node {
+    async fn load(context: &Context, mut head: Head) -> Result<Node, RetrieveError> {
+        context.mut_update_index_head_for_consistency(&mut head).await?;
+
+        let values = head.project_values(&context.slab).await?;
+
+        let mut entries: Vec<(String, SlotId)> =
+            values.iter()
+                  .filter(|(_, composite)| !composite.is_empty())
+                  .filter_map(|(key, composite)| Some((composite.clone(), slot_id(key)?)))
+                  .collect();
+        entries.sort();
+
+        let other = values.iter()
+                          .filter(|(key, _)| key.as_str() != LEAF_KEY && slot_id(key).is_none())
+                          .map(|(key, value)| (key.clone(), value.clone()))
+                          .collect();
+
+        Ok(Node { leaf: values.get(LEAF_KEY).map(|l| l.as_str()) == Some("1"),
+                  head,
+                  entries,
+                  other })
+    }
+
+    /// Create a new node containing the given entries, which are renumbered from slot zero
+    async fn create(context: &Context, leaf: bool, entries: &[(String, SlotId)], edges: &HashMap<SlotId, Head>)
+                    -> Result<Node, WriteError> {
+        let mut renumbered = Vec::new();
+        let mut targets = HashMap::new();
+        for (i, (composite, slot_id)) in entries.iter().enumerate() {
+            renumbered.push((composite.clone(), i as SlotId));
+            if let Some(head) = edges.get(slot_id) {
+                targets.insert(i as SlotId, head.clone());
+            }
+        }
+
+        let node = Node { head: Head::Null,
+                          leaf,
+                          entries: Vec::new(),
+                          other: HashMap::new() };
+
+        node.keyframe(context, leaf, renumbered, &targets).await
+    }
+
+    /// Write a FullyMaterialized memo containing exactly the given entries. A new entity is created for a Null head
+    async fn keyframe(self, context: &Context, leaf: bool, entries: Vec<(String, SlotId)>, edges: &HashMap<SlotId, Head>)
+                      -> Result<Node, WriteError> {
+        let entity_id = match self.head.entity_id() {
+            Some(entity_id) => entity_id,
+            None => context.slab.generate_entity_id(EntityType::IndexNode),
+        };
+
+        let mut values = self.other.clone();
+        values.insert(LEAF_KEY.to_string(), if leaf { "1" } else { "0" }.to_string());
+
+        let mut edgeset = EdgeSet::empty();
+        for (composite, slot_id) in entries.iter() {
+            values.insert(slot_key(*slot_id), composite.clone());
+            if let Some(head) = edges.get(slot_id) {
+                edgeset.insert(*slot_id, head.clone());
+            }
+        }
+
+        let head = context.slab
+                          .new_memo(Some(entity_id),
+                                    self.head,
+                                    MemoBody::FullyMaterialized { v: values,
+                                                                  r: RelationSet::empty(),
+                                                                  e: edgeset,
+                                                                  t: EntityType::IndexNode, })
+                          .to_head();
+
+        context.apply_head(&head).await?;
+
+        let mut entries = entries;
+        entries.sort();
+
+        Ok(Node { head,
+                  leaf,
+                  entries,
+                  other: self.other })
+    }
+
+    /// Move the upper half of the entries into a new sibling, returning the sibling and its separator key
+    async fn split(&mut self, context: &Context) -> Result<(String, Node), WriteError> {
+        let edges = self.edges(context).await?;
+        let mid = self.entries.len() / 2;
+
+        let sibling = Node::create(context, self.leaf, &self.entries[mid..], &edges).await?;
+        let separator = self.entries[mid].0.clone();
+
+        let leaf = self.leaf;
+        let entries = self.entries[..mid].to_vec();
+
+        let lower = Node { head: std::mem::replace(&mut self.head, Head::Null),
+                           leaf,
+                           entries: Vec::new(),
+                           other: std::mem::take(&mut self.other) };
+        *self = lower.keyframe(context, leaf, entries, &edges).await?;
+
+        Ok((separator, sibling))
+    }
+
+    async fn set_entry(&mut self, context: &Context, slot_id: SlotId, composite: String, target: Head)
+                       -> Result<(), WriteError> {
+        let mut values = HashMap::new();
+        values.insert(slot_key(slot_id), composite.clone());
+
+        self.head.set_values(&context.slab, values).await?;
+        self.head.set_edge(&context.slab, slot_id, target);
+        context.apply_head(&self.head).await?;
+
+        self.entries.retain(|(_, s)| *s != slot_id);
+        self.entries.push((composite, slot_id));
+        self.entries.sort();
+
+        Ok(())
+    }
+
+    async fn edge(&mut self, context: &Context, slot_id: SlotId) -> Result<Head, RetrieveError> {
+        match self.head.get_edge(&context.slab, slot_id).await? {
+            Some(head) if head.is_some() => Ok(head),
+            _ => Err(RetrieveError::NotFound),
+        }
+    }
+
+    async fn edges(&self, context: &Context) -> Result<HashMap<SlotId, Head>, RetrieveError> {
+        let mut edges = HashMap::new();
+        for edgelink in self.head.project_occupied_edges(&context.slab).await? {
+            if let EdgeLink::Occupied { slot_id, head } = edgelink {
+                edges.insert(slot_id, head);
+            }
+        }
+
+        Ok(edges)
+    }
+
+    /// The slot of the child whose range includes the given key. The first child also includes all keys below its
+    /// separator
+    fn child_for(&self, composite: &str) -> SlotId {
+        self.entries
+            .iter()
+            .take_while(|(c, _)| c.as_str() <= composite)
+            .last()
+            .or_else(|| self.entries.first())
+            .map(|(_, slot_id)| *slot_id)
+            .expect("branch nodes are never empty")
+    }
+
+    fn free_slot(&self) -> SlotId {
+        (0..ORDER as SlotId).find(|s| !self.entries.iter().any(|(_, slot_id)| slot_id == s))
+                            .expect("nodes are split before they are full")
+    }
+}
+
+fn slot_key(slot_id: SlotId) -> String {
+    format!("k{}", slot_id)
+}
+
+fn slot_id(slot_key: &str) -> Option<SlotId> {
+    slot_key.strip_prefix('k')?.parse().ok()
+}
+
+fn composite_key(key: &OrderedKey, entity_id: EntityId) -> String {
+    format!("{}{:016x}", key.encode(), entity_id.id)
+}
+
+fn key_part(composite: &str) -> &str {
+    &composite[..composite.len() - ENTITY_ID_HEX_LEN]
+}
+
+fn encode_bound(bound: Bound<&OrderedKey>) -> Bound<String> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.encode()),
+        Bound::Excluded(key) => Bound::Excluded(key.encode()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+fn above(encoded: &str, lower: &Bound<String>) -> bool {
+    match lower {
+        Bound::Included(l) => encoded >= l.as_str(),
+        Bound::Excluded(l) => encoded > l.as_str(),
+        Bound::Unbounded => true,
+    }
+}
+
+fn below(encoded: &str, upper: &Bound<String>) -> bool {
+    match upper {
+        Bound::Included(u) => encoded <= u.as_str(),
+        Bound::Excluded(u) => encoded < u.as_str(),
+        Bound::Unbounded => true,
+    }
+}
+
+impl fmt::Debug for IndexOrdered {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("IndexOrdered").finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        index::{
+            IndexOrdered,
+            OrderedKey,
+        },
+        Entity,
+        Network,
+        Slab,
+    };
+
+    #[test]
+    fn ordered_key_encoding() {
+        let keys = vec![OrderedKey::Int(i64::MIN),
+                        OrderedKey::Int(-1),
+                        OrderedKey::Int(0),
+                        OrderedKey::Int(7),
+                        OrderedKey::Int(i64::MAX),
+                        OrderedKey::Float(-1.5),
+                        OrderedKey::Float(0.0),
+                        OrderedKey::Float(2.25),
+                        OrderedKey::from(""),
+                        OrderedKey::from("a"),
+                        OrderedKey::from("a\u{0}"),
+                        OrderedKey::from("ab"),
+                        OrderedKey::from("b"),];
+
+        for pair in keys.windows(2) {
+            assert!(pair[0].encode() < pair[1].encode(), "{:?} < {:?}", pair[0], pair[1]);
+        }
+        for key in keys {
+            assert_eq!(OrderedKey::decode(&key.encode()), Some(key));
+        }
+    }
+
+    #[unbase_test_util::async_test]
+    async fn ordered_range_scan() {
+        let net = Network::create_new_system();
+        let slab_a = Slab::new(&net);
+        let context_a = slab_a.create_context();
+
+        let mut index = IndexOrdered::new(&context_a);
+
+        // Enough to split the root and several of its children. Insert out of order.
+        for i in 0..500i64 {
+            let n = (i * 7919) % 500;
+            let record = Entity::new_with_single_kv(&context_a, "n", &n.to_string()).await.unwrap();
+            index.insert(&context_a, OrderedKey::Int(n), record.head.clone()).await.unwrap();
+        }
+
+        let all = index.range(&context_a, ..).await.unwrap();
+        let keys: Vec<OrderedKey> = all.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, (0..500).map(OrderedKey::Int).collect::<Vec<_>>());
+
+        let mut some = index.range(&context_a, OrderedKey::Int(100)..OrderedKey::Int(110)).await.unwrap();
+        assert_eq!(some.len(), 10);
+        assert_eq!(some[0].0, OrderedKey::Int(100));
+        assert_eq!(some[0].1.get_value(&context_a.slab, "n").await.unwrap(), Some("100".to_string()));
+
+        let rev = index.range_rev(&context_a, OrderedKey::Int(495)..=OrderedKey::Int(600)).await.unwrap();
+        let keys: Vec<OrderedKey> = rev.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, (495..500).rev().map(OrderedKey::Int).collect::<Vec<_>>());
+
+        // Entities may share a key, and are removed individually
+        let dup = Entity::new_with_single_kv(&context_a, "n", "dup").await.unwrap();
+        index.insert(&context_a, OrderedKey::Int(250), dup.head.clone()).await.unwrap();
+        assert_eq!(index.range(&context_a, OrderedKey::Int(250)..=OrderedKey::Int(250)).await.unwrap().len(), 2);
+
+        index.remove(&context_a, &OrderedKey::Int(250), dup.id).await.unwrap();
+        assert_eq!(index.range(&context_a, OrderedKey::Int(250)..=OrderedKey::Int(250)).await.unwrap().len(), 1);
+    }
+
+    #[unbase_test_util::async_test]
+    async fn ordered_string_keys() {
+        let net = Network::create_new_system();
+        let slab_a = Slab::new(&net);
+        let context_a = slab_a.create_context();
+
+        let mut index = IndexOrdered::new(&context_a);
+
+        for name in &["walrus", "aardvark", "tiger", "cow", "tapir"] {
+            let record = Entity::new_with_single_kv(&context_a, "name", name).await.unwrap();
+            index.insert(&context_a, OrderedKey::from(*name), record.head.clone()).await.unwrap();
+        }
+
+        let found = index.range(&context_a, OrderedKey::from("t")..OrderedKey::from("u")).await.unwrap();
+        let names: Vec<OrderedKey> = found.into_iter().map(|(k, _)| k).collect();
+        assert_eq!(names, vec![OrderedKey::from("tapir"), OrderedKey::from("tiger")]);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    context::Context,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    index::{
+        Declared,
+        IndexFixed,
+        IndexKind,
+    },
+    slab::{
+        hash_id,
+        EntityId,
+    },
+};
+
+use std::{
+    collections::HashMap,
+    fmt,
+};
+
+use tracing::debug;
+
+/// A secondary index maps the values of a given field to the entities having that value.
+///
+/// It is built from index nodes, just like the root index: An IndexFixed keyed by the hash of the value leads to a
+/// posting node for that value, which is itself the root of an IndexFixed keyed by entity id. The root and posting
+/// nodes have deterministic ids, such that an index declared on several slabs is one and the same. The root node is
+/// stored in the root index, and the index is listed in the index catalog, which is how other contexts discover that
+/// the index exists.
+pub struct IndexSecondary {
+    field: String,
+    index: IndexFixed,
+}
+
+impl IndexSecondary {
+    /// Create the secondary index for a field, or open it if it already exists
+    pub async fn declare(context: &Context, field: &str) -> Result<IndexSecondary, WriteError> {
+        if let Some(index) = Self::open(context, field).await? {
+            return Ok(index);
+        }
+
+        let index = Self::declare_with_id(context, field, EntityId::secondary_index(field), HashMap::new()).await?;
+
+        let declared = Declared { kind:    IndexKind::Secondary,
+                                  fields:  vec![field.to_string()],
+                                  covered: Vec::new(), };
+        declared.record(context).await?;
+
+        Ok(index)
+    }
+
+    /// Create or open an index with the given root node id, recording the given values on the root node if it is new
+    pub(crate) async fn declare_with_id(context: &Context, field: &str, entity_id: EntityId,
+                                        mut values: HashMap<String, String>)
+                                        -> Result<IndexSecondary, WriteError> {
+        if let Some(index) = Self::open_with_id(context, field, entity_id).await? {
+            return Ok(index);
+        }
+
+        values.insert("field".to_string(), field.to_string());
+
+        let root = Head::new_index_with_id(&context.slab, entity_id, values);
+        context.apply_head(&root).await?;
+        context.update_indices(entity_id, &root).await?;
+
+        Ok(IndexSecondary { field: field.to_string(),
+                            index: IndexFixed::new_from_head(root), })
+    }
+
+    /// Open the secondary index for a field, if one has been declared
+    pub async fn open(context: &Context, field: &str) -> Result<Option<IndexSecondary>, RetrieveError> {
+        Self::open_with_id(context, field, EntityId::secondary_index(field)).await
+    }
+
+    pub(crate) async fn open_with_id(context: &Context, field: &str, entity_id: EntityId)
+                                     -> Result<Option<IndexSecondary>, RetrieveError> {
+        match context.root_index().await?.get(context, entity_id.id).await? {
+            // The root index is keyed by id alone, so make sure we didn't find some other entity
+            Some(head) if head.entity_id() == Some(entity_id) => {
+                Ok(Some(IndexSecondary { field: field.to_string(),
+                                         index: IndexFixed::new_from_head(head), }))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub(crate) fn root_head(&self) -> &Head {
+        self.index.root_head()
+    }
+
+    pub(crate) fn get_root_entity_id(&self) -> EntityId {
+        self.index.get_root_entity_id()
+    }
+
+    /// Add an entity to the posting for the given value
+    pub async fn insert(&mut self, context: &Context, value: &str, head: Head) -> Result<(), WriteError> {
+        let entity_id = head.entity_id().ok_or(WriteError::BadTarget)?;
+        self.insert_entry(context, value, entity_id, head).await
+    }
+
+    /// Add an entry for an entity to the posting for the given value, which leads to the given head. This need not be
+    /// the head of the entity itself.
+    pub(crate) async fn insert_entry(&mut self, context: &Context, value: &str, entity_id: EntityId, head: Head)
+                                     -> Result<(), WriteError> {
+        debug!("IndexSecondary({}).insert({}, {})", self.field, value, entity_id);
+
+        let mut posting = match self.posting(context, value).await? {
+            Some(posting) => posting,
+            None => {
+                let posting_id = EntityId::index_posting(self.index.get_root_entity_id(), value_key(value));
+                let root = Head::new_index_with_id(&context.slab, posting_id, HashMap::new());
+                context.apply_head(&root).await?;
+
+                self.index.insert(context, value_key(value), root.clone()).await?;
+
+                IndexFixed::new_from_head(root)
+            },
+        };
+
+        posting.insert(context, entity_id.id, head).await
+    }
+
+    /// Remove an entity from the posting for the given value
+    pub async fn remove(&mut self, context: &Context, value: &str, entity_id: EntityId) -> Result<(), WriteError> {
+        debug!("IndexSecondary({}).remove({}, {})", self.field, value, entity_id);
+
+        match self.posting(context, value).await? {
+            Some(mut posting) => posting.remove(context, entity_id.id).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Retrieve the heads of all entities listed under the given value. Values are keyed by hash, and entries may be
+    /// stale if the entity was edited by a context which was unaware of the index, so callers must check the value of
+    /// the entity itself. For the same reason, entries may be missing altogether.
+    pub async fn get(&self, context: &Context, value: &str) -> Result<Vec<Head>, RetrieveError> {
+        match self.posting(context, value).await? {
+            Some(posting) => posting.entries(context).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn posting(&self, context: &Context, value: &str) -> Result<Option<IndexFixed>, RetrieveError> {
+        let posting_id = EntityId::index_posting(self.index.get_root_entity_id(), value_key(value));
+
+        match self.index.get(context, value_key(value)).await? {
+            Some(head) if head.entity_id() == Some(posting_id) => Ok(Some(IndexFixed::new_from_head(head))),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Values which share a key share a posting
+fn value_key(value: &str) -> u64 {
+    hash_id(&[value.as_bytes()])
+}
+
+impl fmt::Debug for IndexSecondary {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("IndexSecondary").field("field", &self.field).finish()
+    }
+}
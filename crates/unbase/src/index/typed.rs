@@ -0,0 +1,91 @@
+use crate::{
+    context::Context,
+    error::{
+        RetrieveError,
+        WriteError,
+    },
+    head::Head,
+    index::IndexFixed,
+    slab::{
+        EntityId,
+        EntityType,
+        TypeId,
+    },
+};
+
+use std::{
+    collections::HashMap,
+    fmt,
+};
+
+/// Lists the entities of a single user-defined type, alongside the root index which lists every entity.
+///
+/// It is an IndexFixed keyed by entity id, the root node of which has a deterministic id, such that the index for a
+/// given type is one and the same on every slab. The root node is stored in the root index, which is how other
+/// contexts discover it.
+pub struct IndexTyped {
+    type_id: TypeId,
+    index:   IndexFixed,
+}
+
+impl IndexTyped {
+    /// Create the index for a type, or open it if it already exists. Returns true along with the index if it was
+    /// created.
+    pub(crate) async fn declare(context: &Context, type_id: TypeId) -> Result<(IndexTyped, bool), WriteError> {
+        if let Some(index) = Self::open(context, type_id).await? {
+            return Ok((index, false));
+        }
+
+        let entity_id = EntityId::type_index(type_id);
+
+        let mut values = HashMap::new();
+        values.insert("type".to_string(), type_id.to_string());
+
+        let root = Head::new_index_with_id(&context.slab, entity_id, values);
+        context.apply_head(&root).await?;
+        // Directly into the root index, as the index node is not itself of a user-defined type
+        context.root_index().await?.insert(context, entity_id.id, root.clone()).await?;
+
+        Ok((IndexTyped { type_id,
+                         index: IndexFixed::new_from_head(root) },
+            true))
+    }
+
+    /// Open the index for a type, if any entities of that type have been written
+    pub async fn open(context: &Context, type_id: TypeId) -> Result<Option<IndexTyped>, RetrieveError> {
+        let entity_id = EntityId::type_index(type_id);
+
+        match context.root_index().await?.get(context, entity_id.id).await? {
+            // The root index is keyed by id alone, so make sure we didn't find some other entity
+            Some(head) if head.entity_id() == Some(entity_id) => {
+                Ok(Some(IndexTyped { type_id,
+                                     index: IndexFixed::new_from_head(head) }))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub async fn insert(&mut self, context: &Context, head: Head) -> Result<(), WriteError> {
+        match head.entity_id() {
+            Some(entity_id) if entity_id.stype == EntityType::Custom(self.type_id) => {
+                self.index.insert(context, entity_id.id, head).await
+            },
+            _ => Err(WriteError::BadTarget),
+        }
+    }
+
+    /// Collect the heads of all entities of the type
+    pub async fn entries(&self, context: &Context) -> Result<Vec<Head>, RetrieveError> {
+        self.index.entries(context).await
+    }
+}
+
+impl fmt::Debug for IndexTyped {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("IndexTyped").field("type_id", &self.type_id).finish()
+    }
+}
@@ -60,13 +60,18 @@ extern crate serde_derive;
 extern crate serde;
 extern crate serde_json;
 
+pub mod blob;
 pub mod context;
+pub mod document;
 pub mod entity;
 pub mod error;
 pub mod head;
 pub mod index;
 pub mod network;
+pub mod query;
+pub mod schema;
 pub mod slab;
+pub mod traversal;
 pub mod util;
 
 pub use crate::{
@@ -23,10 +23,13 @@ pub use crate::slab::{
 use crate::util::system_creator::SystemCreator;
 
 use crate::{
+    error::IndexShapeError,
     head::Head,
+    index::IndexShape,
     slab::{
         SlabHandle,
         SlabId,
+        MAX_SLAB_ID,
     },
 };
 use std::{
@@ -56,7 +59,8 @@ pub struct NetworkInner {
     slabs:             RwLock<Vec<SlabHandle>>,
     transports:        RwLock<Vec<Box<dyn Transport + Send + Sync>>>,
     root_index_seed:   RwLock<Option<(Head, SlabRef)>>,
-    create_new_system: bool,
+    /// The shape of the root index, if we are to create a new system rather than join an existing one
+    create_new_system: Option<IndexShape>,
 }
 
 pub struct WeakNetwork(Weak<NetworkInner>);
@@ -66,7 +70,7 @@ impl Network {
     /// This represents your joining an existing unbase system.
     /// (In production, this is the one you want)
     pub fn new() -> Network {
-        Self::new_inner(false)
+        Self::new_inner(None)
     }
 
     /// In test cases, you want to create a wholly new unbase system.
@@ -76,10 +80,20 @@ impl Network {
         // Must this be done with the root of one system subordinating to the root of another?
         // Or perhaps we can allow systems to split AND merge?
 
-        Self::new_inner(true)
+        Self::new_inner(Some(IndexShape::for_key::<u64>()))
     }
 
-    fn new_inner(create_new_system: bool) -> Network {
+    /// Create a wholly new unbase system whose root index has the given fan-out and depth. Small embedded deployments
+    /// may prefer a narrower fan-out, whereas very large ones may want more levels. The shape is recorded in the root
+    /// index seed, and may later be changed with [`Context::reindex`](crate::context::Context::reindex). The root index
+    /// is keyed by entity id, so the shape must cover a u64
+    pub fn create_new_system_with_index_shape(shape: IndexShape) -> Result<Network, IndexShapeError> {
+        shape.check::<u64>()?;
+
+        Ok(Self::new_inner(Some(shape)))
+    }
+
+    fn new_inner(create_new_system: Option<IndexShape>) -> Network {
         let net = Network(Arc::new(NetworkInner { next_slab_id: RwLock::new(0),
                                                   slabs: RwLock::new(Vec::new()),
                                                   transports: RwLock::new(Vec::new()),
@@ -94,6 +108,7 @@ impl Network {
 
     // TODO: remove this when slab ids are randomly generated
     pub fn hack_set_next_slab_id(&self, id: SlabId) {
+        assert!(id <= MAX_SLAB_ID, "slab ids may not exceed MAX_SLAB_ID");
         *self.next_slab_id.write().unwrap() = id;
     }
 
@@ -118,6 +133,7 @@ impl Network {
     pub fn generate_slab_id(&self) -> u32 {
         let mut next_slab_id = self.next_slab_id.write().unwrap();
         let id = *next_slab_id;
+        assert!(id <= MAX_SLAB_ID, "slab ids are exhausted");
         *next_slab_id += 1;
 
         id
@@ -254,9 +270,9 @@ impl Network {
             }
         }
 
-        if self.create_new_system {
+        if let Some(shape) = self.create_new_system {
             // I'm a new system, so I can do this!
-            let seed = SystemCreator::generate_root_index_seed(slab);
+            let seed = SystemCreator::generate_root_index_seed(slab, shape);
             *self.root_index_seed.write().unwrap() = Some((seed.clone(), slab.my_ref.clone()));
             return true;
         }
@@ -1,28 +0,0 @@
-//use context::Context;
-//use entity::{EntityId,EntityField};
-//
-//pub struct Query{
-//    parts: Vec<QueryPart>,
-//    context: Context
-//}
-//pub struct QueryPart {
-//    fields: Vec<EntityField>,
-//    criteria: QueryPartCriteria
-//}
-//pub enum QueryPartCriteria {
-//    ByEntityId: { id: Vec<EntityId> }
-//    ByFieldComparison
-//}
-//
-//impl Query {
-//    pub fn new (context: &Context) -> Query {
-//        Query {
-//            parts:   Vec::new(),
-//            context: context
-//        }
-//    }
-//
-//    pub fn add_part {
-//
-//    }
-//}
@@ -0,0 +1,93 @@
+use super::compare_values;
+
+use std::cmp::Ordering;
+
+/// An aggregate function, computed over the entities which match a query (or each group thereof)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Aggregate {
+    /// The number of matching entities
+    Count,
+    /// The total of the numeric values of the field
+    Sum(String),
+    /// The least value of the field, compared numerically when possible
+    Min(String),
+    /// The greatest value of the field, compared numerically when possible
+    Max(String),
+    /// The mean of the numeric values of the field
+    Avg(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregateValue {
+    Count(usize),
+    Number(f64),
+    Value(String),
+    /// There were no values to aggregate
+    Null,
+}
+
+/// The outcome of an aggregation query for one value of the GROUP BY field. Ungrouped queries produce a single
+/// group with no key. The values are in the order of the aggregates of the query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group {
+    /// The value of the GROUP BY field, or None for entities without one
+    pub key:    Option<String>,
+    pub values: Vec<AggregateValue>,
+}
+
+impl Aggregate {
+    /// The field which is aggregated, if any
+    pub fn field(&self) -> Option<&str> {
+        match *self {
+            Aggregate::Count => None,
+            Aggregate::Sum(ref field)
+            | Aggregate::Min(ref field)
+            | Aggregate::Max(ref field)
+            | Aggregate::Avg(ref field) => Some(field),
+        }
+    }
+}
+
+/// The running state of a single aggregate
+#[derive(Default)]
+pub(super) struct Accumulator {
+    count:   usize,
+    sum:     f64,
+    numbers: usize,
+    min:     Option<String>,
+    max:     Option<String>,
+}
+
+impl Accumulator {
+    pub(super) fn add(&mut self, value: Option<&str>) {
+        self.count += 1;
+
+        let value = match value {
+            Some(value) => value,
+            None => return,
+        };
+
+        if let Ok(number) = value.parse::<f64>() {
+            self.sum += number;
+            self.numbers += 1;
+        }
+
+        if self.min.as_deref().is_none_or(|min| compare_values(value, min) == Ordering::Less) {
+            self.min = Some(value.to_string());
+        }
+        if self.max.as_deref().is_none_or(|max| compare_values(value, max) == Ordering::Greater) {
+            self.max = Some(value.to_string());
+        }
+    }
+
+    pub(super) fn finish(self, aggregate: &Aggregate) -> AggregateValue {
+        match aggregate {
+            Aggregate::Count => AggregateValue::Count(self.count),
+            Aggregate::Sum(_) if self.numbers > 0 => AggregateValue::Number(self.sum),
+            Aggregate::Avg(_) if self.numbers > 0 => AggregateValue::Number(self.sum / self.numbers as f64),
+            Aggregate::Min(_) => self.min.map_or(AggregateValue::Null, AggregateValue::Value),
+            Aggregate::Max(_) => self.max.map_or(AggregateValue::Null, AggregateValue::Value),
+            _ => AggregateValue::Null,
+        }
+    }
+}
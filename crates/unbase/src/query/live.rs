@@ -0,0 +1,241 @@
+use crate::{
+    context::Context,
+    error::RetrieveError,
+    head::Head,
+    query::{
+        Query,
+        Row,
+    },
+    slab::{
+        EntityId,
+        EntityType,
+        MemoBody,
+    },
+};
+
+use futures::{
+    channel::mpsc,
+    future::{
+        FutureExt,
+        RemoteHandle,
+    },
+    stream,
+    task::{
+        Context as TaskContext,
+        Poll,
+    },
+    Stream,
+    StreamExt,
+    TryStreamExt,
+};
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    fmt,
+    pin::Pin,
+    sync::atomic::Ordering,
+};
+
+/// A change to the result set of a live query
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryEvent {
+    /// An entity which now matches the query, including those which matched initially
+    Added(Row),
+    /// An entity which still matches the query, but whose selected values have changed
+    Changed(Row),
+    /// An entity which no longer matches the query
+    Removed(EntityId),
+}
+
+/// A stream of [`QueryEvent`]s for a query which is kept up to date as entities are written. Where the query has only
+/// filters, only the entities which were written are evaluated again, otherwise the whole query is. The query is
+/// abandoned when the stream is dropped.
+pub struct LiveQuery {
+    events: mpsc::UnboundedReceiver<Result<QueryEvent, RetrieveError>>,
+    _task:  RemoteHandle<()>,
+}
+
+impl LiveQuery {
+    pub(crate) fn new(query: Query) -> Self {
+        let (events_tx, events) = mpsc::unbounded();
+        let task = crate::util::task::spawn_with_handle(run(query, events_tx));
+
+        LiveQuery { events,
+                    _task: task }
+    }
+}
+
+async fn run(query: Query, events: mpsc::UnboundedSender<Result<QueryEvent, RetrieveError>>) {
+    let context = query.context.clone();
+
+    // Index heads applied to the context tell us of the entities written here, or whose index entries arrived from
+    // elsewhere. Entities in the result set are observed too, so that we hear about remote edits for which we have not
+    // (yet) received the index nodes
+    let (applied_tx, applied) = mpsc::channel(1000);
+    let lagged = context.observe_applied(applied_tx);
+    let (entity_tx, entities) = mpsc::channel(1000);
+    let mut notices = stream::select(applied, entities);
+
+    let mut observed: HashSet<EntityId> = HashSet::new();
+    let mut current: HashMap<EntityId, Row> = HashMap::new();
+    let mut rerun = true;
+
+    loop {
+        if rerun {
+            let outcome = match query.clone().rows().try_collect::<Vec<Row>>().await {
+                Ok(rows) => {
+                    let mut previous = std::mem::take(&mut current);
+                    let mut outcome = Ok(());
+
+                    for row in rows {
+                        previous.remove(&row.entity_id);
+                        outcome = outcome.and(update(&mut current, row.entity_id, Some(row), &events));
+                    }
+                    for entity_id in previous.into_keys() {
+                        outcome = outcome.and(send(&events, Ok(QueryEvent::Removed(entity_id))));
+                    }
+
+                    outcome
+                },
+                Err(e) => send(&events, Err(e)),
+            };
+            if outcome.is_err() {
+                return;
+            }
+        }
+
+        // A snapshot never changes, and its entities must not reflect edits made since
+        if !context.is_read_only() {
+            for entity_id in current.keys() {
+                if observed.insert(*entity_id) {
+                    context.slab.observe_entity(*entity_id, entity_tx.clone());
+                }
+            }
+        }
+
+        // Wait for something to change, and coalesce whatever else is already pending
+        let mut heads = match notices.next().await {
+            Some(head) => vec![head],
+            None => return,
+        };
+        while let Some(Some(head)) = notices.next().now_or_never() {
+            heads.push(head);
+        }
+
+        let changed = if query.is_incremental() && !lagged.swap(false, Ordering::SeqCst) {
+            changed_entities(&heads)
+        } else {
+            None
+        };
+
+        match changed {
+            Some(changed) => {
+                rerun = false;
+
+                for (entity_id, head) in changed {
+                    let outcome = match query.row_for(head).await {
+                        Ok(row) => update(&mut current, entity_id, row, &events),
+                        Err(e) => send(&events, Err(e)),
+                    };
+                    if outcome.is_err() {
+                        return;
+                    }
+                }
+            },
+            // Whether an entity matches depends on others, or we missed some of the heads, or can't tell which
+            // entities they concern
+            None => rerun = true,
+        }
+    }
+}
+
+/// The entities written by the given heads, along with their heads as of that write. Index heads are read for the
+/// entities they list, which is None should any of their memos not be resident.
+fn changed_entities(heads: &[Head]) -> Option<HashMap<EntityId, Head>> {
+    let mut changed = HashMap::new();
+
+    for head in heads {
+        match head.entity_id() {
+            Some(EntityId { stype: EntityType::IndexNode, .. }) => {
+                for memoref in head.iter() {
+                    let memo = memoref.get_memo_if_resident()?;
+
+                    let edges = match memo.body {
+                        MemoBody::Edge(ref e)
+                        | MemoBody::FullyMaterialized { ref e, .. }
+                        | MemoBody::PartiallyMaterialized { ref e, .. } => e,
+                        _ => continue,
+                    };
+
+                    for target in edges.0.values() {
+                        match target.entity_id() {
+                            Some(EntityId { stype: EntityType::IndexNode, .. }) | None => {},
+                            Some(entity_id) => {
+                                changed.insert(entity_id, target.clone());
+                            },
+                        }
+                    }
+                }
+            },
+            Some(entity_id) => {
+                changed.insert(entity_id, head.clone());
+            },
+            None => {},
+        }
+    }
+
+    Some(changed)
+}
+
+/// Record the row of an entity, or that it no longer matches, and report the difference. Err if nobody is listening.
+fn update(current: &mut HashMap<EntityId, Row>, entity_id: EntityId, row: Option<Row>,
+          events: &mpsc::UnboundedSender<Result<QueryEvent, RetrieveError>>)
+          -> Result<(), ()> {
+    let event = match row {
+        Some(row) => {
+            let event = match current.get(&entity_id) {
+                None => Some(QueryEvent::Added(row.clone())),
+                Some(was) if was.values != row.values => Some(QueryEvent::Changed(row.clone())),
+                Some(_) => None,
+            };
+            current.insert(entity_id, row);
+            event
+        },
+        None => current.remove(&entity_id).map(|_| QueryEvent::Removed(entity_id)),
+    };
+
+    match event {
+        Some(event) => send(events, Ok(event)),
+        None => Ok(()),
+    }
+}
+
+fn send(events: &mpsc::UnboundedSender<Result<QueryEvent, RetrieveError>>, event: Result<QueryEvent, RetrieveError>)
+        -> Result<(), ()> {
+    events.unbounded_send(event).map_err(|_| ())
+}
+
+impl Stream for LiveQuery {
+    type Item = Result<QueryEvent, RetrieveError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for LiveQuery {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("LiveQuery").finish()
+    }
+}
+
+impl Context {
+    /// Evaluate a query against this context, and continue to report changes to its result set. The initial results
+    /// are reported as additions.
+    pub fn live_query(&self, mut query: Query) -> LiveQuery {
+        query.context = self.clone();
+        LiveQuery::new(query)
+    }
+}
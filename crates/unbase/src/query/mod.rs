@@ -0,0 +1,717 @@
+//! Declarative queries against a [`Context`].
+//!
+//! A [`Query`] is a pipeline of steps which is applied to each candidate entity in turn: Filters discard entities
+//! which don't match a [`Predicate`], and traversals replace the entity with the one referenced by a given relation
+//! slot. Candidates are drawn from a composite index when the query has equality predicates on all of its fields (see
+//! [`Context::declare_composite_index`]), from a secondary index when it has an equality predicate on a field which
+//! has been declared via [`Context::declare_index`], from the matching range of an ordered index when it compares a
+//! field which has been declared via [`Context::declare_ordered_index`], from the per-type index when the query is for
+//! a user-defined type, and from a scan of the root index otherwise. Queries whose predicates and selected fields are
+//! all carried by a covering index are answered from the index alone. Results are streamed, in no particular order, and are subject
+//! to the same consistency guarantees as any other read from the context.
+//!
+//! Matching entities may be summarized with [`Aggregate`]s, optionally grouped by the value of a field, via
+//! [`Query::groups`].
+//!
+//! Queries may also be written in a small SQL-like language, see [`Query::parse`].
+//!
+//! ```
+//! # use unbase::{Network, Slab, Entity};
+//! # use futures::StreamExt;
+//! # async_std::task::block_on(async {
+//! # let net = Network::create_new_system();
+//! # let slab = Slab::new(&net);
+//! # let context = slab.create_context();
+//! Entity::new_with_single_kv(&context, "beast", "Tiger").await.unwrap();
+//!
+//! let found: Vec<_> = context.query().eq("beast", "Tiger").limit(10).stream().collect().await;
+//! assert_eq!(found.len(), 1);
+//! # });
+//! ```
+
+mod aggregate;
+mod live;
+mod parse;
+
+pub use self::aggregate::{
+    Aggregate,
+    AggregateValue,
+    Group,
+};
+pub use self::live::{
+    LiveQuery,
+    QueryEvent,
+};
+
+use self::{
+    aggregate::Accumulator,
+    parse::parse,
+};
+use crate::{
+    context::Context,
+    entity::Entity,
+    error::{
+        QueryError,
+        RetrieveError,
+    },
+    head::Head,
+    index::{
+        IndexComposite,
+        IndexKind,
+        IndexOrdered,
+        IndexSecondary,
+        IndexTyped,
+    },
+    schema::Schema,
+    slab::{
+        EntityId,
+        EntityType,
+        SlotId,
+    },
+};
+
+use futures::stream::{
+    self,
+    BoxStream,
+    StreamExt,
+    TryStreamExt,
+};
+use std::{
+    cmp::Ordering,
+    collections::{
+        HashMap,
+        HashSet,
+        VecDeque,
+    },
+    fmt,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Predicate {
+    Eq(String, String),
+    Compare(String, Comparison, String),
+    Prefix(String, String),
+    Exists(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Filter(Predicate),
+    Traverse(SlotId),
+}
+
+/// The selected values of an entity which matched a query
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub entity_id: EntityId,
+    pub values:    HashMap<String, String>,
+}
+
+#[derive(Clone)]
+pub struct Query {
+    context:    Context,
+    stype:      Option<EntityType>,
+    steps:      Vec<Step>,
+    fields:     Option<Vec<String>>,
+    aggregates: Vec<Aggregate>,
+    group_by:   Option<String>,
+    limit:      Option<usize>,
+    offset:     usize,
+}
+
+impl Query {
+    pub fn new(context: &Context) -> Self {
+        Query { context:    context.clone(),
+                stype:      None,
+                steps:      Vec::new(),
+                fields:     None,
+                aggregates: Vec::new(),
+                group_by:   None,
+                limit:      None,
+                offset:     0, }
+    }
+
+    /// Parse a query written in the query language:
+    ///
+    /// ```text
+    /// SELECT <* | item, ...> [FROM <Record | type name>]
+    ///     [WHERE <condition> [AND <condition> ...]]
+    ///     [FOLLOW <slot> [WHERE <condition> [AND <condition> ...]] ...]
+    ///     [GROUP BY <field>] [LIMIT <n>] [OFFSET <n>]
+    ///
+    /// item      := field | COUNT(*) | SUM(field) | MIN(field) | MAX(field) | AVG(field)
+    /// condition := field (= | != | <> | < | <= | > | >=) value
+    ///            | field LIKE 'prefix%'
+    ///            | field EXISTS
+    /// ```
+    ///
+    /// For example `SELECT name FROM Record WHERE beast = 'Tiger'`. Type names other than `Record` must have a
+    /// registered [`Schema`], which is used to check the fields of the query. Queries which select aggregates or
+    /// have a GROUP BY clause are executed with [`groups`](Query::groups), and may only select the GROUP BY field
+    /// alongside their aggregates.
+    pub async fn parse(context: &Context, text: &str) -> Result<Query, QueryError> {
+        let statement = parse(text)?;
+        let mut query = Query::new(context);
+
+        let mut schema = None;
+        if let Some(from) = statement.from {
+            if from.item == "Record" {
+                query = query.of_type(EntityType::Record);
+            } else {
+                let type_id = Schema::type_id_for(&from.item);
+                match context.get_schema(type_id).await? {
+                    Some(s) => schema = Some(s),
+                    None => {
+                        return Err(QueryError::Plan { position: from.position,
+                                                      message:  format!("unknown type `{}`", from.item), })
+                    },
+                }
+                query = query.of_type(EntityType::Custom(type_id));
+            }
+        }
+
+        let check_field = |position: usize, field: &str| {
+            match schema {
+                Some(ref schema) if !schema.has_field(field) => {
+                    Err(QueryError::Plan { position,
+                                           message: format!("type `{}` has no field `{}`", schema.name, field) })
+                },
+                _ => Ok(()),
+            }
+        };
+
+        // Selected fields belong to the entities yielded by the final stage
+        if statement.stages.len() == 1 {
+            for field in statement.fields.iter().flatten() {
+                check_field(field.position, &field.item)?;
+            }
+            for aggregate in statement.aggregates.iter() {
+                if let Some(field) = aggregate.item.field() {
+                    check_field(aggregate.position, field)?;
+                }
+            }
+            if let Some(ref group_by) = statement.group_by {
+                check_field(group_by.position, &group_by.item)?;
+            }
+        }
+
+        if !statement.aggregates.is_empty() || statement.group_by.is_some() {
+            let group_by = statement.group_by.as_ref().map(|g| g.item.as_str());
+            for field in statement.fields.iter().flatten() {
+                if Some(field.item.as_str()) != group_by {
+                    return Err(QueryError::Plan { position: field.position,
+                                                  message:  format!("field `{}` must appear in GROUP BY",
+                                                                    field.item), });
+                }
+            }
+        }
+
+        for stage in statement.stages {
+            if let Some(slot_id) = stage.follow {
+                query = query.traverse(slot_id);
+            }
+            for condition in stage.conditions {
+                if stage.follow.is_none() {
+                    check_field(condition.position, condition.item.field())?;
+                }
+                query = query.filter(condition.item);
+            }
+        }
+
+        if let Some(fields) = statement.fields {
+            query.fields = Some(fields.into_iter().map(|f| f.item).collect());
+        }
+        query.aggregates = statement.aggregates.into_iter().map(|a| a.item).collect();
+        query.group_by = statement.group_by.map(|g| g.item);
+        query.limit = statement.limit;
+        query.offset = statement.offset;
+
+        Ok(query)
+    }
+
+    /// Only consider entities of the given type. Otherwise all records and user-defined types are considered.
+    pub fn of_type(mut self, stype: EntityType) -> Self {
+        self.stype = Some(stype);
+        self
+    }
+
+    pub fn filter(mut self, predicate: Predicate) -> Self {
+        self.steps.push(Step::Filter(predicate));
+        self
+    }
+
+    pub fn eq(self, field: &str, value: &str) -> Self {
+        self.filter(Predicate::Eq(field.to_string(), value.to_string()))
+    }
+
+    pub fn compare(self, field: &str, comparison: Comparison, value: &str) -> Self {
+        self.filter(Predicate::Compare(field.to_string(), comparison, value.to_string()))
+    }
+
+    pub fn prefix(self, field: &str, prefix: &str) -> Self {
+        self.filter(Predicate::Prefix(field.to_string(), prefix.to_string()))
+    }
+
+    pub fn exists(self, field: &str) -> Self {
+        self.filter(Predicate::Exists(field.to_string()))
+    }
+
+    /// Follow the relation in the given slot. Subsequent steps apply to the related entity, and entities without
+    /// such a relation are discarded.
+    pub fn traverse(mut self, slot_id: SlotId) -> Self {
+        self.steps.push(Step::Traverse(slot_id));
+        self
+    }
+
+    /// Restrict the values returned by [`rows`](Query::rows) to the given fields
+    pub fn select(mut self, fields: &[&str]) -> Self {
+        self.fields = Some(fields.iter().map(|f| f.to_string()).collect());
+        self
+    }
+
+    /// Compute the given aggregate with [`groups`](Query::groups)
+    pub fn aggregate(mut self, aggregate: Aggregate) -> Self {
+        self.aggregates.push(aggregate);
+        self
+    }
+
+    /// Compute the aggregates separately for each value of the given field
+    pub fn group_by(mut self, field: &str) -> Self {
+        self.group_by = Some(field.to_string());
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Execute the query, yielding the matching entities
+    pub fn stream(self) -> BoxStream<'static, Result<Entity, RetrieveError>> {
+        let execution = Execution { context:    self.context,
+                                    stype:      self.stype,
+                                    steps:      self.steps,
+                                    candidates: None,
+                                    seen:       HashSet::new(),
+                                    skip:       self.offset,
+                                    limit:      self.limit, };
+
+        stream::unfold(Some(execution), |execution| {
+            async move {
+                let mut execution = execution?;
+
+                match execution.next().await {
+                    Ok(Some(entity)) => Some((Ok(entity), Some(execution))),
+                    Ok(None) => None,
+                    // Stop at the first error
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        }).boxed()
+    }
+
+    /// Execute the query, yielding the selected values of the matching entities, or all values if none were selected
+    pub fn rows(self) -> BoxStream<'static, Result<Row, RetrieveError>> {
+        stream::once(async move {
+            match self.covering_index().await {
+                Ok(Some((index, values))) => stream::iter(self.covered_rows(index, values).await).boxed(),
+                Ok(None) => self.entity_rows(),
+                Err(e) => stream::iter(vec![Err(e)]).boxed(),
+            }
+        }).flatten()
+          .boxed()
+    }
+
+    /// A covering index which carries every value needed to evaluate the query, along with the values to look up
+    async fn covering_index(&self) -> Result<Option<(IndexComposite, HashMap<String, String>)>, RetrieveError> {
+        let fields = match self.fields {
+            Some(ref fields) => fields,
+            None => return Ok(None),
+        };
+        if !self.steps.iter().all(|step| matches!(step, Step::Filter(Predicate::Eq(..)))) {
+            return Ok(None);
+        }
+
+        let equalities = equalities(&self.steps);
+        match open_composite(&self.context, &equalities).await? {
+            Some(index)
+                if self.steps.iter().all(|step| matches!(step, Step::Filter(p) if index.records(p.field())))
+                   && fields.iter().all(|field| index.records(field)) =>
+            {
+                Ok(Some((index, equalities)))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    async fn covered_rows(&self, index: IndexComposite, values: HashMap<String, String>) -> Vec<Result<Row, RetrieveError>> {
+        let covered = match index.get_covered(&self.context, &values).await {
+            Ok(covered) => covered,
+            Err(e) => return vec![Err(e)],
+        };
+
+        let fields = self.fields.clone().unwrap_or_default();
+        let mut seen = HashSet::new();
+
+        covered.into_iter()
+               .filter(|c| is_candidate(self.stype, Some(c.entity_id)) && seen.insert(c.entity_id))
+               .filter(|c| {
+                   self.steps.iter().all(|step| {
+                                        match step {
+                                            Step::Filter(p) => p.matches(c.values.get(p.field()).map(|v| v.as_str())),
+                                            Step::Traverse(_) => false,
+                                        }
+                                    })
+               })
+               .skip(self.offset)
+               .take(self.limit.unwrap_or(usize::MAX))
+               .map(|mut c| {
+                   c.values.retain(|k, _| fields.contains(k));
+                   Ok(Row { entity_id: c.entity_id,
+                            values:    c.values, })
+               })
+               .collect()
+    }
+
+    fn entity_rows(self) -> BoxStream<'static, Result<Row, RetrieveError>> {
+        let fields = self.fields.clone();
+
+        self.stream()
+            .and_then(move |entity| {
+                let fields = fields.clone();
+                async move { entity_row(entity, fields.as_deref()).await }
+            })
+            .boxed()
+    }
+
+    /// Whether the result set may be kept up to date by evaluating only those entities which have changed, as their
+    /// membership depends on nothing else
+    pub(crate) fn is_incremental(&self) -> bool {
+        self.limit.is_none() && self.offset == 0 && self.steps.iter().all(|step| matches!(step, Step::Filter(_)))
+    }
+
+    /// Evaluate the query against a single entity, yielding its row should it match
+    pub(crate) async fn row_for(&self, head: Head) -> Result<Option<Row>, RetrieveError> {
+        let execution = Execution { context:    self.context.clone(),
+                                    stype:      self.stype,
+                                    steps:      self.steps.clone(),
+                                    candidates: None,
+                                    seen:       HashSet::new(),
+                                    skip:       0,
+                                    limit:      None, };
+
+        match execution.evaluate(head).await? {
+            Some(entity) => Ok(Some(entity_row(entity, self.fields.as_deref()).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Execute the query, computing its aggregates over the matching entities. Groups are ordered by their key, and
+    /// the limit and offset of the query apply to the groups rather than the entities.
+    pub async fn groups(mut self) -> Result<Vec<Group>, RetrieveError> {
+        let limit = self.limit.take();
+        let offset = std::mem::take(&mut self.offset);
+        let aggregates = std::mem::take(&mut self.aggregates);
+        let group_by = self.group_by.take();
+
+        let new_accumulators = || aggregates.iter().map(|_| Accumulator::default()).collect::<Vec<_>>();
+
+        // Aggregating over no entities at all still produces a value, unless grouped
+        let mut groups: HashMap<Option<String>, Vec<Accumulator>> = HashMap::new();
+        if group_by.is_none() {
+            groups.insert(None, new_accumulators());
+        }
+
+        let mut entities = self.stream();
+        while let Some(entity) = entities.next().await {
+            let values = entity?.get_values().await?;
+
+            let key = group_by.as_ref().and_then(|field| values.get(field).cloned());
+            let accumulators = groups.entry(key).or_insert_with(new_accumulators);
+
+            for (aggregate, accumulator) in aggregates.iter().zip(accumulators.iter_mut()) {
+                accumulator.add(aggregate.field().and_then(|field| values.get(field)).map(|v| v.as_str()));
+            }
+        }
+
+        let mut groups: Vec<Group> =
+            groups.into_iter()
+                  .map(|(key, accumulators)| {
+                      let values = aggregates.iter().zip(accumulators).map(|(a, acc)| acc.finish(a)).collect();
+                      Group { key, values }
+                  })
+                  .collect();
+
+        groups.sort_by(|a, b| {
+                  match (&a.key, &b.key) {
+                      (Some(a), Some(b)) => compare_values(a, b),
+                      (a, b) => a.cmp(b),
+                  }
+              });
+
+        Ok(groups.into_iter().skip(offset).take(limit.unwrap_or(usize::MAX)).collect())
+    }
+}
+
+impl fmt::Debug for Query {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Query")
+           .field("stype", &self.stype)
+           .field("steps", &self.steps)
+           .field("fields", &self.fields)
+           .field("aggregates", &self.aggregates)
+           .field("group_by", &self.group_by)
+           .field("limit", &self.limit)
+           .field("offset", &self.offset)
+           .finish()
+    }
+}
+
+impl Predicate {
+    pub fn field(&self) -> &str {
+        match *self {
+            Predicate::Eq(ref field, _)
+            | Predicate::Compare(ref field, ..)
+            | Predicate::Prefix(ref field, _)
+            | Predicate::Exists(ref field) => field,
+        }
+    }
+
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        let value = match value {
+            Some(value) => value,
+            None => return false,
+        };
+
+        match *self {
+            Predicate::Eq(_, ref expected) => value == expected,
+            Predicate::Compare(_, comparison, ref operand) => {
+                let ordering = compare_values(value, operand);
+                match comparison {
+                    Comparison::Ne => ordering != Ordering::Equal,
+                    Comparison::Lt => ordering == Ordering::Less,
+                    Comparison::Le => ordering != Ordering::Greater,
+                    Comparison::Gt => ordering == Ordering::Greater,
+                    Comparison::Ge => ordering != Ordering::Less,
+                }
+            },
+            Predicate::Prefix(_, ref prefix) => value.starts_with(prefix.as_str()),
+            Predicate::Exists(_) => true,
+        }
+    }
+}
+
+/// The selected values of an entity, or all of them if none were selected
+async fn entity_row(mut entity: Entity, fields: Option<&[String]>) -> Result<Row, RetrieveError> {
+    let mut values = entity.get_values().await?;
+    if let Some(fields) = fields {
+        values.retain(|k, _| fields.contains(k));
+    }
+
+    Ok(Row { entity_id: entity.id,
+             values })
+}
+
+/// Values are compared numerically when both are numbers, and lexically otherwise
+fn compare_values(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// Whether an entity may be yielded by a query for the given type, or any record or user-defined type if None
+fn is_candidate(stype: Option<EntityType>, entity_id: Option<EntityId>) -> bool {
+    match (entity_id, stype) {
+        (Some(entity_id), Some(stype)) => entity_id.stype == stype,
+        (Some(EntityId { stype: EntityType::Record, .. }), None)
+        | (Some(EntityId { stype: EntityType::Custom(_), .. }), None) => true,
+        _ => false,
+    }
+}
+
+/// The equality predicates which precede any traversal, and thus concern the candidate entities themselves
+fn equalities(steps: &[Step]) -> HashMap<String, String> {
+    steps.iter()
+         .take_while(|step| !matches!(step, Step::Traverse(_)))
+         .filter_map(|step| {
+             match step {
+                 Step::Filter(Predicate::Eq(field, value)) => Some((field.clone(), value.clone())),
+                 _ => None,
+             }
+         })
+         .collect()
+}
+
+/// Open the declared composite index over the most of the given fields, if there is one
+async fn open_composite(context: &Context, equalities: &HashMap<String, String>)
+                        -> Result<Option<IndexComposite>, RetrieveError> {
+    let declared = context.declared_indexes().await?;
+
+    let widest = declared.iter()
+                         .filter(|index| index.kind == IndexKind::Composite)
+                         .filter(|index| index.fields.iter().all(|field| equalities.contains_key(field)))
+                         .max_by_key(|index| index.fields.len());
+
+    match widest {
+        Some(index) => {
+            let fields: Vec<&str> = index.fields.iter().map(|f| f.as_str()).collect();
+            IndexComposite::open(context, &fields).await
+        },
+        None => Ok(None),
+    }
+}
+
+struct Execution {
+    context:    Context,
+    stype:      Option<EntityType>,
+    steps:      Vec<Step>,
+    candidates: Option<VecDeque<Head>>,
+    seen:       HashSet<EntityId>,
+    skip:       usize,
+    limit:      Option<usize>,
+}
+
+impl Execution {
+    async fn next(&mut self) -> Result<Option<Entity>, RetrieveError> {
+        if self.limit == Some(0) {
+            return Ok(None);
+        }
+
+        if self.candidates.is_none() {
+            self.candidates = Some(self.plan().await?);
+        }
+
+        while let Some(head) = self.candidates.as_mut().unwrap().pop_front() {
+            let entity = match self.evaluate(head).await? {
+                Some(entity) => entity,
+                None => continue,
+            };
+
+            // Traversals may lead several candidates to the same entity
+            if !self.seen.insert(entity.id) {
+                continue;
+            }
+
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+
+            if let Some(ref mut limit) = self.limit {
+                *limit -= 1;
+            }
+
+            return Ok(Some(entity));
+        }
+
+        Ok(None)
+    }
+
+    /// Choose the candidate entities, using a composite, secondary or ordered index if one applies
+    async fn plan(&self) -> Result<VecDeque<Head>, RetrieveError> {
+        let equalities = equalities(&self.steps);
+        if let Some(index) = open_composite(&self.context, &equalities).await? {
+            return Ok(index.get(&self.context, &equalities).await?.into_iter().collect());
+        }
+
+        let declared = self.context.declared_indexes().await?;
+        for step in self.steps.iter() {
+            match step {
+                Step::Filter(Predicate::Eq(field, value)) => {
+                    if !declared.iter().any(|index| index.is_on(IndexKind::Secondary, field)) {
+                        continue;
+                    }
+                    if let Some(index) = IndexSecondary::open(&self.context, field).await? {
+                        return Ok(index.get(&self.context, value).await?.into_iter().collect());
+                    }
+                },
+                Step::Filter(_) => {},
+                // Predicates after a traversal concern some other entity
+                Step::Traverse(_) => break,
+            }
+        }
+
+        for step in self.steps.iter() {
+            match step {
+                Step::Filter(Predicate::Compare(field, comparison, value)) if *comparison != Comparison::Ne => {
+                    if !declared.iter().any(|index| index.is_on(IndexKind::Ordered, field)) {
+                        continue;
+                    }
+                    if let Some(index) = IndexOrdered::open(&self.context, field).await? {
+                        return Ok(index.candidates(&self.context, *comparison, value).await?.into_iter().collect());
+                    }
+                },
+                Step::Filter(_) => {},
+                Step::Traverse(_) => break,
+            }
+        }
+
+        // Entities of a user-defined type are listed by type, so need not be picked out of the root index, unless the
+        // index for the type is nowhere to be found
+        let typed = match self.stype {
+            Some(EntityType::Custom(type_id)) => IndexTyped::open(&self.context, type_id).await?,
+            _ => None,
+        };
+
+        let entries = match typed {
+            Some(index) => index.entries(&self.context).await?,
+            None => self.context.root_index().await?.entries(&self.context).await?,
+        };
+
+        Ok(entries.into_iter().filter(|head| self.is_candidate(head.entity_id())).collect())
+    }
+
+    fn is_candidate(&self, entity_id: Option<EntityId>) -> bool {
+        is_candidate(self.stype, entity_id)
+    }
+
+    async fn evaluate(&self, head: Head) -> Result<Option<Entity>, RetrieveError> {
+        // Index postings may list entities of any type
+        if !self.is_candidate(head.entity_id()) {
+            return Ok(None);
+        }
+
+        let mut entity = self.context.get_entity_from_head(head).await?;
+
+        for step in self.steps.iter() {
+            match step {
+                Step::Filter(predicate) => {
+                    let value = entity.get_value(predicate.field()).await?;
+                    if !predicate.matches(value.as_deref()) {
+                        return Ok(None);
+                    }
+                },
+                Step::Traverse(slot_id) => {
+                    entity = match entity.get_relation(*slot_id).await? {
+                        Some(related) => related,
+                        None => return Ok(None),
+                    };
+                },
+            }
+        }
+
+        Ok(Some(entity))
+    }
+}
+
+impl Context {
+    /// Begin a [`Query`] against this context
+    pub fn query(&self) -> Query {
+        Query::new(self)
+    }
+}
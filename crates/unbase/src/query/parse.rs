@@ -0,0 +1,446 @@
+//! A small SQL-like language for ad-hoc queries:
+//!
+//! ```text
+//! SELECT <* | item, ...> [FROM <Record | type name>]
+//!     [WHERE <condition> [AND <condition> ...]]
+//!     [FOLLOW <slot> [WHERE <condition> [AND <condition> ...]] ...]
+//!     [GROUP BY <field>] [LIMIT <n>] [OFFSET <n>]
+//!
+//! item      := field | COUNT(*) | SUM(field) | MIN(field) | MAX(field) | AVG(field)
+//! condition := field (= | != | <> | < | <= | > | >=) value
+//!            | field LIKE 'prefix%'
+//!            | field EXISTS
+//! ```
+//!
+//! Values are either 'quoted strings' (with '' as an escaped quote) or numbers. Keywords and aggregate names are case
+//! insensitive.
+
+use crate::{
+    error::QueryError,
+    query::{
+        Aggregate,
+        Comparison,
+        Predicate,
+    },
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Statement {
+    /// None for SELECT *
+    pub fields:     Option<Vec<Positioned<String>>>,
+    pub aggregates: Vec<Positioned<Aggregate>>,
+    pub from:       Option<Positioned<String>>,
+    pub stages:     Vec<Stage>,
+    pub group_by:   Option<Positioned<String>>,
+    pub limit:      Option<usize>,
+    pub offset:     usize,
+}
+
+/// The conditions which apply before the first FOLLOW, or after each one
+#[derive(Debug, PartialEq)]
+pub(crate) struct Stage {
+    pub follow:     Option<u8>,
+    pub conditions: Vec<Positioned<Predicate>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Positioned<T> {
+    pub position: usize,
+    pub item:     T,
+}
+
+enum SelectItem {
+    Field(Positioned<String>),
+    Aggregate(Positioned<Aggregate>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Number(String),
+    Symbol(&'static str),
+    End,
+}
+
+const SYMBOLS: &[&str] = &["<=", ">=", "!=", "<>", "=", "<", ">", ",", "*", "(", ")"];
+
+fn error(position: usize, message: String) -> QueryError {
+    QueryError::Parse { position, message }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some(&(position, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '\'' {
+            chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\'')) => {
+                        // A doubled quote is an escaped quote
+                        if let Some(&(_, '\'')) = chars.peek() {
+                            chars.next();
+                            string.push('\'');
+                        } else {
+                            break;
+                        }
+                    },
+                    Some((_, c)) => string.push(c),
+                    None => return Err(error(position, "unterminated string".to_string())),
+                }
+            }
+            tokens.push((position, Token::Str(string)));
+        } else if c.is_ascii_digit() || c == '-' || c == '.' {
+            let mut number = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_ascii_digit() || c == '.' || (c == '-' && number.is_empty()) {
+                    number.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if number.parse::<f64>().is_err() {
+                return Err(error(position, format!("invalid number `{}`", number)));
+            }
+            tokens.push((position, Token::Number(number)));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    word.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push((position, Token::Word(word)));
+        } else {
+            let symbol = SYMBOLS.iter()
+                                .copied()
+                                .find(|symbol| text[position..].starts_with(symbol))
+                                .ok_or_else(|| error(position, format!("unexpected character `{}`", c)))?;
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            tokens.push((position, Token::Symbol(symbol)));
+        }
+    }
+
+    tokens.push((text.len(), Token::End));
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next:   usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].1
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.next].0
+    }
+
+    fn advance(&mut self) -> (usize, Token) {
+        let token = self.tokens[self.next].clone();
+        if token.1 != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Token::Word(word) => word.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T, QueryError> {
+        let found = match self.peek() {
+            Token::Word(word) => format!("`{}`", word),
+            Token::Str(string) => format!("'{}'", string),
+            Token::Number(number) => number.clone(),
+            Token::Symbol(symbol) => format!("`{}`", symbol),
+            Token::End => "end of query".to_string(),
+        };
+
+        Err(error(self.position(), format!("expected {}, found {}", expected, found)))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if self.is_keyword(keyword) {
+            self.advance();
+            Ok(())
+        } else {
+            self.unexpected(keyword)
+        }
+    }
+
+    fn optional_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn identifier(&mut self) -> Result<Positioned<String>, QueryError> {
+        match self.peek().clone() {
+            Token::Word(word) => {
+                let (position, _) = self.advance();
+                Ok(Positioned { position, item: word })
+            },
+            _ => self.unexpected("an identifier"),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Positioned<usize>, QueryError> {
+        if let Token::Number(number) = self.peek().clone() {
+            if let Ok(n) = number.parse() {
+                let (position, _) = self.advance();
+                return Ok(Positioned { position, item: n });
+            }
+        }
+
+        self.unexpected("a non-negative integer")
+    }
+
+    fn symbol(&mut self, symbol: &'static str) -> Result<(), QueryError> {
+        if *self.peek() == Token::Symbol(symbol) {
+            self.advance();
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", symbol))
+        }
+    }
+
+    fn value(&mut self) -> Result<String, QueryError> {
+        match self.peek().clone() {
+            Token::Str(value) | Token::Number(value) => {
+                self.advance();
+                Ok(value)
+            },
+            _ => self.unexpected("a value"),
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, QueryError> {
+        self.keyword("SELECT")?;
+
+        let mut aggregates = Vec::new();
+        let fields = if let Token::Symbol("*") = self.peek() {
+            self.advance();
+            None
+        } else {
+            let mut fields = Vec::new();
+            loop {
+                match self.select_item()? {
+                    SelectItem::Field(field) => fields.push(field),
+                    SelectItem::Aggregate(aggregate) => aggregates.push(aggregate),
+                }
+
+                if let Token::Symbol(",") = self.peek() {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            Some(fields)
+        };
+
+        let from = if self.optional_keyword("FROM") { Some(self.identifier()?) } else { None };
+
+        let mut stages = vec![Stage { follow:     None,
+                                      conditions: self.conditions()?, }];
+
+        while self.optional_keyword("FOLLOW") {
+            let slot = self.integer()?;
+            if slot.item > u8::MAX as usize {
+                return Err(error(slot.position, format!("relation slot {} is out of range", slot.item)));
+            }
+
+            stages.push(Stage { follow:     Some(slot.item as u8),
+                                conditions: self.conditions()?, });
+        }
+
+        let group_by = if self.optional_keyword("GROUP") {
+            self.keyword("BY")?;
+            Some(self.identifier()?)
+        } else {
+            None
+        };
+
+        let limit = if self.optional_keyword("LIMIT") { Some(self.integer()?.item) } else { None };
+        let offset = if self.optional_keyword("OFFSET") { self.integer()?.item } else { 0 };
+
+        if *self.peek() != Token::End {
+            return self.unexpected("end of query");
+        }
+
+        Ok(Statement { fields,
+                       aggregates,
+                       from,
+                       stages,
+                       group_by,
+                       limit,
+                       offset })
+    }
+
+    fn select_item(&mut self) -> Result<SelectItem, QueryError> {
+        let name = self.identifier()?;
+        if *self.peek() != Token::Symbol("(") {
+            return Ok(SelectItem::Field(name));
+        }
+        self.advance();
+
+        let aggregate = match name.item.to_ascii_lowercase().as_str() {
+            "count" => {
+                self.symbol("*")?;
+                Aggregate::Count
+            },
+            "sum" => Aggregate::Sum(self.identifier()?.item),
+            "min" => Aggregate::Min(self.identifier()?.item),
+            "max" => Aggregate::Max(self.identifier()?.item),
+            "avg" => Aggregate::Avg(self.identifier()?.item),
+            _ => return Err(error(name.position, format!("unknown aggregate `{}`", name.item))),
+        };
+        self.symbol(")")?;
+
+        Ok(SelectItem::Aggregate(Positioned { position: name.position,
+                                              item:     aggregate, }))
+    }
+
+    fn conditions(&mut self) -> Result<Vec<Positioned<Predicate>>, QueryError> {
+        let mut conditions = Vec::new();
+
+        if self.optional_keyword("WHERE") {
+            conditions.push(self.condition()?);
+            while self.optional_keyword("AND") {
+                conditions.push(self.condition()?);
+            }
+        }
+
+        Ok(conditions)
+    }
+
+    fn condition(&mut self) -> Result<Positioned<Predicate>, QueryError> {
+        let Positioned { position, item: field } = self.identifier()?;
+
+        let predicate = if self.optional_keyword("EXISTS") {
+            Predicate::Exists(field)
+        } else if self.is_keyword("LIKE") {
+            self.advance();
+            let pattern_position = self.position();
+            let pattern = self.value()?;
+
+            match pattern.find('%') {
+                Some(i) if i == pattern.len() - 1 => Predicate::Prefix(field, pattern[..i].to_string()),
+                _ => {
+                    return Err(error(pattern_position,
+                                     "only prefix patterns of the form 'prefix%' are supported".to_string()))
+                },
+            }
+        } else {
+            let comparison = match self.peek() {
+                Token::Symbol("=") => None,
+                Token::Symbol("!=") | Token::Symbol("<>") => Some(Comparison::Ne),
+                Token::Symbol("<") => Some(Comparison::Lt),
+                Token::Symbol("<=") => Some(Comparison::Le),
+                Token::Symbol(">") => Some(Comparison::Gt),
+                Token::Symbol(">=") => Some(Comparison::Ge),
+                _ => return self.unexpected("a comparison, LIKE or EXISTS"),
+            };
+            self.advance();
+
+            match comparison {
+                None => Predicate::Eq(field, self.value()?),
+                Some(comparison) => Predicate::Compare(field, comparison, self.value()?),
+            }
+        };
+
+        Ok(Positioned { position,
+                        item: predicate })
+    }
+}
+
+pub(crate) fn parse(text: &str) -> Result<Statement, QueryError> {
+    let mut parser = Parser { tokens: tokenize(text)?,
+                              next:   0, };
+
+    parser.statement()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_statement() {
+        let statement = parse("select name, legs from Record where beast = 'Tiger' and legs >= 4 \
+                               follow 2 where name like 'Ti%' limit 10 offset 5")
+                        .unwrap();
+
+        let fields: Vec<_> = statement.fields.unwrap().into_iter().map(|f| f.item).collect();
+        assert_eq!(fields, vec!["name", "legs"]);
+        assert_eq!(statement.from.unwrap().item, "Record");
+        assert_eq!(statement.limit, Some(10));
+        assert_eq!(statement.offset, 5);
+
+        assert_eq!(statement.stages.len(), 2);
+        assert_eq!(statement.stages[0].follow, None);
+        assert_eq!(statement.stages[0].conditions[0].item,
+                   Predicate::Eq("beast".to_string(), "Tiger".to_string()));
+        assert_eq!(statement.stages[0].conditions[1].item,
+                   Predicate::Compare("legs".to_string(), Comparison::Ge, "4".to_string()));
+        assert_eq!(statement.stages[1].follow, Some(2));
+        assert_eq!(statement.stages[1].conditions[0].item,
+                   Predicate::Prefix("name".to_string(), "Ti".to_string()));
+
+        let statement = parse("SELECT * WHERE quote = 'it''s' AND venomous EXISTS").unwrap();
+        assert!(statement.fields.is_none());
+        assert_eq!(statement.stages[0].conditions[0].item,
+                   Predicate::Eq("quote".to_string(), "it's".to_string()));
+        assert_eq!(statement.stages[0].conditions[1].item, Predicate::Exists("venomous".to_string()));
+
+        let statement = parse("SELECT beast, COUNT(*), avg(legs) GROUP BY beast LIMIT 3").unwrap();
+        let fields: Vec<_> = statement.fields.unwrap().into_iter().map(|f| f.item).collect();
+        let aggregates: Vec<_> = statement.aggregates.into_iter().map(|a| a.item).collect();
+        assert_eq!(fields, vec!["beast"]);
+        assert_eq!(aggregates, vec![Aggregate::Count, Aggregate::Avg("legs".to_string())]);
+        assert_eq!(statement.group_by.unwrap().item, "beast");
+        assert_eq!(statement.limit, Some(3));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let position = |text| {
+            match parse(text) {
+                Err(QueryError::Parse { position, .. }) => position,
+                other => panic!("expected a parse error, got {:?}", other),
+            }
+        };
+
+        assert_eq!(position("SELEC name"), 0);
+        assert_eq!(position("SELECT name WHERE beast = "), 26);
+        assert_eq!(position("SELECT name WHERE beast ~ 'Tiger'"), 24);
+        assert_eq!(position("SELECT name WHERE beast = 'Tiger"), 26);
+        assert_eq!(position("SELECT name WHERE beast LIKE '%iger'"), 29);
+        assert_eq!(position("SELECT name FOLLOW 300"), 19);
+        assert_eq!(position("SELECT name LIMIT 5 garbage"), 20);
+        assert_eq!(position("SELECT median(legs)"), 7);
+        assert_eq!(position("SELECT count(legs)"), 13);
+        assert_eq!(position("SELECT sum(legs GROUP BY beast"), 16);
+        assert_eq!(position("SELECT count(*) GROUP beast"), 22);
+    }
+}
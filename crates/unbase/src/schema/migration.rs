@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+};
+
+/// The reserved key under which each memo written to a user-defined type records the schema version it was written in
+pub const VERSION_KEY: &str = "_v";
+
+type Transform = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+enum Step {
+    Rename(String, String),
+    Transform(String, Transform),
+    Remove(String),
+}
+
+/// Migration describes how to reshape values written under one schema version into the next.
+///
+/// Migrations are registered on a [`Context`](crate::context::Context), and are applied to old-version memos as they
+/// are projected. Slabs which are still writing the old shape thus need not be upgraded in lockstep.
+#[derive(Clone)]
+pub struct Migration {
+    pub from: u32,
+    pub to:   u32,
+    steps:    Vec<Arc<Step>>,
+}
+
+impl Migration {
+    pub fn new(from: u32, to: u32) -> Self {
+        assert!(to > from, "Migrations must move to a newer schema version");
+
+        Migration { from,
+                    to,
+                    steps: Vec::new() }
+    }
+
+    pub fn rename(mut self, from: &str, to: &str) -> Self {
+        self.steps.push(Arc::new(Step::Rename(from.to_string(), to.to_string())));
+        self
+    }
+
+    pub fn transform<F>(mut self, field: &str, f: F) -> Self
+        where F: Fn(&str) -> String + Send + Sync + 'static
+    {
+        self.steps.push(Arc::new(Step::Transform(field.to_string(), Arc::new(f))));
+        self
+    }
+
+    pub fn remove(mut self, field: &str) -> Self {
+        self.steps.push(Arc::new(Step::Remove(field.to_string())));
+        self
+    }
+
+    /// Reshape the values of a single memo. Steps are applied in the order they were declared
+    pub fn apply(&self, values: &mut HashMap<String, String>) {
+        for step in self.steps.iter() {
+            match **step {
+                Step::Rename(ref from, ref to) => {
+                    if let Some(value) = values.remove(from) {
+                        values.insert(to.clone(), value);
+                    }
+                },
+                Step::Transform(ref field, ref f) => {
+                    if let Some(value) = values.get_mut(field) {
+                        *value = f(value);
+                    }
+                },
+                Step::Remove(ref field) => {
+                    values.remove(field);
+                },
+            }
+        }
+
+        values.insert(VERSION_KEY.to_string(), self.to.to_string());
+    }
+}
+
+/// Apply the chain of migrations necessary to bring values written at their recorded version up to `version`.
+/// Values with no recorded version are considered to be version 1. If the chain is broken, the values are left at the
+/// newest version which could be reached.
+pub(crate) fn migrate_values(migrations: &[Migration], values: &mut HashMap<String, String>, version: u32) {
+    let mut current = values.get(VERSION_KEY).and_then(|v| v.parse().ok()).unwrap_or(1);
+
+    while current < version {
+        match migrations.iter().find(|m| m.from == current) {
+            Some(migration) => {
+                migration.apply(values);
+                current = migration.to;
+            },
+            None => break,
+        }
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Migration")
+           .field("from", &self.from)
+           .field("to", &self.to)
+           .field("steps", &self.steps.len())
+           .finish()
+    }
+}
@@ -0,0 +1,249 @@
+pub mod migration;
+
+pub use self::migration::Migration;
+
+use crate::{
+    error::SchemaViolation,
+    slab::{
+        EntityId,
+        EntityType,
+        SlotId,
+        TypeId,
+    },
+};
+
+use sha2::{
+    Digest,
+    Sha256,
+};
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    fmt,
+};
+
+/// The value types which a Schema may declare for a given field.
+/// Values are stored as strings regardless, but must parse as the declared type in order to be written
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    /// Binary data, which may only be written via `Entity::set_blob`
+    Blob,
+}
+
+/// The permissible target of a relation slot
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RelationTarget {
+    Any,
+    Record,
+    Type(TypeId),
+}
+
+/// Schema declares the allowed keys, value types, and relation targets for a user-defined entity type.
+///
+/// Schemas are themselves stored as entities of `EntityType::Schema` under a deterministic
+/// [`EntityId`](crate::slab::EntityId), so they replicate just like any other data. Writes to entities of type
+/// `EntityType::Custom(type_id)` are validated against the Schema registered for that type.
+///
+/// Each version of a schema replaces the last. Memos record the version they were written under, and are brought up to
+/// date on read by any [`Migration`]s registered on the context.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Schema {
+    pub name:    String,
+    pub type_id: TypeId,
+    pub version: u32,
+    fields:      BTreeMap<String, ValueType>,
+    relations:   BTreeMap<SlotId, RelationTarget>,
+}
+
+impl Schema {
+    /// Create an empty Schema. The TypeId is derived from the name, such that slabs which independently
+    /// register the same type will agree on its identity
+    pub fn new(name: &str) -> Self {
+        Schema { name:      name.to_string(),
+                 type_id:   Self::type_id_for(name),
+                 version:   1,
+                 fields:    BTreeMap::new(),
+                 relations: BTreeMap::new(), }
+    }
+
+    pub fn type_id_for(name: &str) -> TypeId {
+        let hash = Sha256::digest(name.as_bytes());
+        (hash[0] as TypeId) << 24 | (hash[1] as TypeId) << 16 | (hash[2] as TypeId) << 8 | hash[3] as TypeId
+    }
+
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn field(mut self, name: &str, value_type: ValueType) -> Self {
+        self.fields.insert(name.to_string(), value_type);
+        self
+    }
+
+    pub fn relation(mut self, slot_id: SlotId, target: RelationTarget) -> Self {
+        self.relations.insert(slot_id, target);
+        self
+    }
+
+    /// The EntityType of entities described by this Schema
+    pub fn entity_type(&self) -> EntityType {
+        EntityType::Custom(self.type_id)
+    }
+
+    /// The EntityId under which this Schema is stored
+    pub fn entity_id(&self) -> EntityId {
+        EntityId::schema(self.type_id)
+    }
+
+    pub fn has_field(&self, name: &str) -> bool {
+        self.fields.contains_key(name)
+    }
+
+    pub fn validate_value(&self, key: &str, value: &str) -> Result<(), SchemaViolation> {
+        let value_type = self.fields
+                             .get(key)
+                             .ok_or_else(|| SchemaViolation::UnknownField(key.to_string()))?;
+
+        let valid = match value_type {
+            ValueType::String => true,
+            ValueType::Integer => value.parse::<i64>().is_ok(),
+            ValueType::Float => value.parse::<f64>().is_ok(),
+            ValueType::Boolean => value == "true" || value == "false",
+            ValueType::Blob => false,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(SchemaViolation::InvalidValue { field:    key.to_string(),
+                                                expected: *value_type, })
+        }
+    }
+
+    pub fn validate_blob(&self, key: &str) -> Result<(), SchemaViolation> {
+        match self.fields.get(key) {
+            Some(ValueType::Blob) => Ok(()),
+            Some(value_type) => {
+                Err(SchemaViolation::InvalidValue { field:    key.to_string(),
+                                                    expected: *value_type, })
+            },
+            None => Err(SchemaViolation::UnknownField(key.to_string())),
+        }
+    }
+
+    pub fn validate_values(&self, values: &HashMap<String, String>) -> Result<(), SchemaViolation> {
+        for (key, value) in values.iter() {
+            self.validate_value(key, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn validate_relation(&self, slot_id: SlotId, target: EntityId) -> Result<(), SchemaViolation> {
+        let valid = match self.relations.get(&slot_id) {
+            None => return Err(SchemaViolation::UnknownRelation(slot_id)),
+            Some(RelationTarget::Any) => true,
+            Some(RelationTarget::Record) => target.stype == EntityType::Record,
+            Some(RelationTarget::Type(type_id)) => target.stype == EntityType::Custom(*type_id),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(SchemaViolation::InvalidRelationTarget { slot_id,
+                                                         target: target.stype })
+        }
+    }
+
+    /// Render this Schema as the values of a Schema entity
+    pub(crate) fn to_values(&self) -> HashMap<String, String> {
+        let mut values = HashMap::new();
+        values.insert("name".to_string(), self.name.clone());
+        values.insert("version".to_string(), self.version.to_string());
+
+        for (field, value_type) in self.fields.iter() {
+            values.insert(format!("field.{}", field), value_type.to_string());
+        }
+        for (slot_id, target) in self.relations.iter() {
+            values.insert(format!("relation.{}", slot_id), target.to_string());
+        }
+
+        values
+    }
+
+    /// Parse the projected values of a Schema entity. Returns None if they don't describe a valid Schema
+    pub(crate) fn from_values(values: &HashMap<String, String>) -> Option<Self> {
+        let mut schema = Schema::new(values.get("name")?).version(values.get("version")?.parse().ok()?);
+
+        for (key, value) in values.iter() {
+            if let Some(field) = key.strip_prefix("field.") {
+                schema.fields.insert(field.to_string(), value.parse().ok()?);
+            } else if let Some(slot_id) = key.strip_prefix("relation.") {
+                schema.relations.insert(slot_id.parse().ok()?, value.parse().ok()?);
+            }
+        }
+
+        Some(schema)
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ValueType::String => "string",
+            ValueType::Integer => "integer",
+            ValueType::Float => "float",
+            ValueType::Boolean => "boolean",
+            ValueType::Blob => "blob",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for ValueType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "string" => Ok(ValueType::String),
+            "integer" => Ok(ValueType::Integer),
+            "float" => Ok(ValueType::Float),
+            "boolean" => Ok(ValueType::Boolean),
+            "blob" => Ok(ValueType::Blob),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for RelationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RelationTarget::Any => write!(f, "any"),
+            RelationTarget::Record => write!(f, "record"),
+            RelationTarget::Type(type_id) => write!(f, "type.{}", type_id),
+        }
+    }
+}
+
+impl std::str::FromStr for RelationTarget {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "any" => Ok(RelationTarget::Any),
+            "record" => Ok(RelationTarget::Record),
+            _ => {
+                match s.strip_prefix("type.") {
+                    Some(type_id) => type_id.parse().map(RelationTarget::Type).map_err(|_| ()),
+                    None => Err(()),
+                }
+            },
+        }
+    }
+}
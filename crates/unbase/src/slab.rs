@@ -1,6 +1,9 @@
 pub use self::{
     common_structs::*,
-    handle::SlabHandle,
+    handle::{
+        SlabHandle,
+        PREFETCH_TIMEOUT,
+    },
     memo::{
         serde as memo_serde,
         Memo,
@@ -15,6 +18,7 @@ pub use self::{
         MemoRefPtr,
     },
     slabref::{
+        serde as slabref_serde,
         SlabRef,
         SlabRefInner,
     },
@@ -51,6 +55,10 @@ mod slabref;
 
 pub type SlabId = u32;
 
+/// Slab ids are limited to 31 bits. Counter-based memo ids carry the slab id in their upper half, so this leaves the
+/// high bit free for memo ids derived from content, such as those of chunks
+pub const MAX_SLAB_ID: SlabId = u32::MAX >> 1;
+
 #[derive(Clone)]
 pub struct Slab {
     pub id:           SlabId,
@@ -7,7 +7,10 @@ use std::{
     },
 };
 
-use tracing::debug;
+use tracing::{
+    debug,
+    warn,
+};
 
 use crate::{
     error::StorageOpDeclined,
@@ -19,6 +22,11 @@ use crate::{
         TransportAddress,
     },
     slab::{
+        memo::{
+            chunk_memo_id,
+            chunk_tree_memo_id,
+            genesis_memo_id,
+        },
         state::SlabState,
         EdgeSet,
         EntityId,
@@ -120,6 +128,79 @@ impl SlabAgent {
         memoref
     }
 
+    /// A memo which is sent to other slabs, but is not stored by either. See [`MemoBody::is_transient`]
+    pub fn new_transient_memo(&self, body: MemoBody) -> MemoRef {
+        let memo_id = {
+            let mut state = self.state.write().unwrap();
+            state.counters.last_memo_id += 1;
+            (self.id as u64).rotate_left(32) | state.counters.last_memo_id as u64
+        };
+
+        Self::transient_memoref(Memo::new(MemoInner { id: memo_id,
+                                                      owning_slab_id: self.id,
+                                                      entity_id: None,
+                                                      parents: Head::Null,
+                                                      body }))
+    }
+
+    fn transient_memoref(memo: Memo) -> MemoRef {
+        MemoRef(Arc::new(MemoRefInner { id:             memo.id,
+                                        owning_slab_id: memo.owning_slab_id,
+                                        entity_id:      memo.entity_id,
+                                        peerlist:       RwLock::new(MemoPeerList(Vec::new())),
+                                        ptr:            RwLock::new(MemoRefPtr::Resident(memo)), }))
+    }
+
+    /// Chunk memos are content-addressed, so storing the same bytes more than once yields the same memo
+    pub fn new_chunk_memo(&self, data: Vec<u8>) -> MemoRef {
+        let memo = Memo::new(MemoInner { id:             chunk_memo_id(&data),
+                                         owning_slab_id: self.id,
+                                         entity_id:      None,
+                                         parents:        Head::Null,
+                                         body:           MemoBody::Chunk(data), });
+
+        let (memoref, had_memoref) = self.assert_memoref(memo.id, None, MemoPeerList(Vec::new()), Some(memo));
+        if !had_memoref {
+            self.consider_emit_memo(&memoref);
+        }
+
+        memoref
+    }
+
+    /// ChunkTree memos are likewise content-addressed, by the ids of the memos beneath them
+    pub fn new_chunk_tree_memo(&self, children: Vec<MemoRef>) -> MemoRef {
+        let memo = Memo::new(MemoInner { id:             chunk_tree_memo_id(&children),
+                                         owning_slab_id: self.id,
+                                         entity_id:      None,
+                                         parents:        Head::Null,
+                                         body:           MemoBody::ChunkTree(Head::Anonymous { owning_slab_id: self.id,
+                                                                                               head:           children, }), });
+
+        let (memoref, had_memoref) = self.assert_memoref(memo.id, None, MemoPeerList(Vec::new()), Some(memo));
+        if !had_memoref {
+            self.consider_emit_memo(&memoref);
+        }
+
+        memoref
+    }
+
+    /// The genesis memo of an entity with a deterministic id. The body must likewise be deterministic, such that every
+    /// slab creating this entity yields the same memo
+    pub fn new_genesis_memo(&self, entity_id: EntityId, body: MemoBody) -> MemoRef {
+        let memo = Memo::new(MemoInner { id:             genesis_memo_id(entity_id),
+                                         owning_slab_id: self.id,
+                                         entity_id:      Some(entity_id),
+                                         parents:        Head::Null,
+                                         body });
+
+        let (memoref, had_memoref) = self.assert_memoref(memo.id, memo.entity_id, MemoPeerList(Vec::new()), Some(memo));
+        if !had_memoref {
+            self.consider_emit_memo(&memoref);
+        }
+
+        memoref
+    }
+
     pub fn generate_entity_id(&self, stype: EntityType) -> EntityId {
         let mut state = self.state.write().unwrap();
         state.counters.last_entity_id += 1;
@@ -179,9 +260,17 @@ impl SlabAgent {
         rx
     }
 
+    /// Contexts subscribe to the index nodes we receive, so our peers are asked for their context exchanges too
     pub fn observe_index(&self, tx: mpsc::Sender<Head>) {
-        let mut state = self.state.write().unwrap();
-        state.index_subscriptions.push(tx);
+        let first = {
+            let mut state = self.state.write().unwrap();
+            state.index_subscriptions.push(tx);
+            state.index_subscriptions.len() == 1
+        };
+
+        if first {
+            self.send_to_peers(&self.new_transient_memo(MemoBody::ContextSubscribe));
+        }
     }
 
     #[tracing::instrument]
@@ -226,6 +315,12 @@ impl SlabAgent {
                     reply = true;
                 }
 
+                // Slabs which we hear from after our contexts were created must be asked for their exchanges too
+                let has_contexts = !self.state.read().unwrap().index_subscriptions.is_empty();
+                if has_contexts {
+                    origin_slabref.send(&self.my_ref, &self.new_transient_memo(MemoBody::ContextSubscribe));
+                }
+
                 if reply {
                     if let Ok(mentioned_slabref) = self.slabref_from_presence(presence) {
                         // TODO: should we be telling the origin slabref, or the presence slabref that we're here?
@@ -290,6 +385,24 @@ impl SlabAgent {
                     }
                 }
             },
+            MemoBody::ContextExchange(ref heads) => {
+                // The index node heads of another slab's context. Our own contexts apply them just as they would any
+                // index node memo we receive, such that their reads are consistent with the writes of that context.
+                let mut state = self.state.write().unwrap();
+                for head in heads.iter() {
+                    if let Some(EntityId { stype: EntityType::IndexNode,
+                                           .. }) = head.entity_id()
+                    {
+                        Self::notify_index_subscribers(&mut state, head.clone());
+                    }
+                }
+            },
+            MemoBody::ContextSubscribe => {
+                let mut state = self.state.write().unwrap();
+                if !state.exchange_subscribers.iter().any(|s| s.slab_id == origin_slabref.slab_id) {
+                    state.exchange_subscribers.push(origin_slabref.clone());
+                }
+            },
             _ => {},
         }
     }
@@ -373,16 +486,11 @@ impl SlabAgent {
             if let EntityType::IndexNode = entity_id.stype {
                 // TODO3 - update this to consider popularity of this node, and/or common points of reference with a
                 // given context selective hearing?
+                Self::notify_index_subscribers(&mut state, memoref.to_head());
+            }
 
-                let senders = &mut state.index_subscriptions;
+            if let Some(ref mut senders) = state.entity_subscriptions.get_mut(&entity_id) {
                 let len = senders.len();
-
-                // TODO POSTMERGE - alright, this approach isn't going to work.
-                //    fn send(&mut self, item: Item) -> Send<'_, Self, Item>
-                // it returns a Send future which contains &mut self
-                // so collecting these futures won't work unless we clone...
-                // and maybe not even then, because the clones won't live long enough for &mut self
-
                 for i in (0..len).rev() {
                     let r = { senders[i].try_send(memoref.to_head()) };
 
@@ -393,36 +501,60 @@ impl SlabAgent {
                             if e.is_disconnected() {
                                 senders.swap_remove(i);
                             } else {
-                                panic!("one of the index_subscriptions queues is full, and I haven't implemented async sending \
-                                        yet")
+                                // The subscriber has fallen behind. Better that it misses this head than we block
+                                warn!("dropping a head of {} for an entity subscriber whose queue is full", entity_id);
                             }
                         },
                     }
                 }
             }
+        }
 
-            if let Some(ref mut senders) = state.entity_subscriptions.get_mut(&entity_id) {
-                let len = senders.len();
-                for i in (0..len).rev() {
-                    let r = { senders[i].try_send(memoref.to_head()) };
+        //        join_all(futs).await;
+    }
 
-                    match r {
-                        Ok(_) => {},
-                        Err(e) => {
-                            // the fact that SendError.kind is private is :facepalm:
-                            if e.is_disconnected() {
-                                senders.swap_remove(i);
-                            } else {
-                                panic!("one of the entity_subscriptions queues is full, and I haven't implemented async sending \
-                                        yet")
-                            }
-                        },
+    fn notify_index_subscribers(state: &mut SlabState, head: Head) {
+        let senders = &mut state.index_subscriptions;
+        let len = senders.len();
+
+        // TODO POSTMERGE - alright, this approach isn't going to work.
+        //    fn send(&mut self, item: Item) -> Send<'_, Self, Item>
+        // it returns a Send future which contains &mut self
+        // so collecting these futures won't work unless we clone...
+        // and maybe not even then, because the clones won't live long enough for &mut self
+
+        for i in (0..len).rev() {
+            let r = { senders[i].try_send(head.clone()) };
+
+            match r {
+                Ok(_) => {},
+                Err(e) => {
+                    // the fact that SendError.kind is private is :facepalm:
+                    if e.is_disconnected() {
+                        senders.swap_remove(i);
+                    } else {
+                        // The context has fallen behind. Better that it misses this head than we block
+                        warn!("dropping an index head for a context whose queue is full");
                     }
-                }
+                },
             }
         }
+    }
 
-        //        join_all(futs).await;
+    /// Send a memo to every peer slab, whether or not it does peering
+    pub fn send_to_peers(&self, memoref: &MemoRef) {
+        let state = self.state.read().unwrap();
+        for peer_ref in state.peer_refs.iter() {
+            peer_ref.send(&self.my_ref, memoref);
+        }
+    }
+
+    /// Send a memo to every peer slab which has asked for our context exchanges
+    pub fn send_to_exchange_subscribers(&self, memoref: &MemoRef) {
+        let state = self.state.read().unwrap();
+        for subscriber in state.exchange_subscribers.iter() {
+            subscriber.send(&self.my_ref, memoref);
+        }
     }
 
     #[tracing::instrument]
@@ -473,6 +605,13 @@ impl SlabAgent {
             return (*memoref).clone();
         }
 
+        // Transient memos are handled upon arrival, and go no further
+        if let MemoRefPtr::Resident(ref memo) = *memoref.ptr.read().unwrap() {
+            if memo.body.is_transient() {
+                return Self::transient_memoref(self.localize_memo(memo, from_slabref, &MemoPeerList(Vec::new())));
+            }
+        }
+
         // Because our from_slabref is already owned by the destination slab, there is no need to do
         // peerlist.clone_for_slab
         let peerlist = memoref.get_peerlist_for_peer(from_slabref, Some(self.id));
@@ -492,6 +631,25 @@ impl SlabAgent {
         memoref
     }
 
+    /// Obtain a memoref for a memo known only by its id, noting that the given slab holds it should we not already
+    /// have it. Used when a memo id arrives out of band, such as in a causal token. Slabs we aren't peered with are
+    /// ignored, as we would have no way to reach them.
+    pub fn memoref_held_by(&self, memo_id: MemoId, entity_id: Option<EntityId>, slab_id: SlabId) -> MemoRef {
+        let slabref = {
+            let state = self.state.read().unwrap();
+            state.peer_refs.iter().find(|r| r.0.slab_id == slab_id).cloned()
+        };
+
+        let peerlist = MemoPeerList::new(slabref.into_iter()
+                                                .map(|slabref| {
+                                                    MemoPeer { slabref,
+                                                               status: MemoPeeringStatus::Resident }
+                                                })
+                                                .collect());
+
+        self.assert_memoref(memo_id, entity_id, peerlist, None).0
+    }
+
     #[tracing::instrument]
     pub fn localize_memo(&self, memo: &Memo, from_slabref: &SlabRef, peerlist: &MemoPeerList) -> Memo {
         assert!(from_slabref.owning_slab_id == self.id,
@@ -522,6 +680,13 @@ impl SlabAgent {
                                          parents,
                                          body });
 
+        if memo.body.is_transient() {
+            let memoref = Self::transient_memoref(memo.clone());
+            self.handle_memo_from_other_slab(&memo, &memoref, origin_slabref);
+
+            return (memo, memoref, false);
+        }
+
         let (memoref, had_memoref) = self.assert_memoref(memo.id, memo.entity_id, peerlist.clone(), Some(memo.clone()));
 
         {
@@ -591,6 +756,19 @@ impl SlabAgent {
             &MemoBody::MemoRequest(ref memo_ids, ref slabref) => {
                 MemoBody::MemoRequest(memo_ids.clone(), self.localize_slabref(slabref))
             },
+            MemoBody::Chunk(data) => MemoBody::Chunk(data.clone()),
+            MemoBody::ChunkTree(head) => MemoBody::ChunkTree(self.localize_head(head, from_slabref, false)),
+            MemoBody::Blob { k, l, s, f, c } => {
+                MemoBody::Blob { k: k.clone(),
+                                 l: *l,
+                                 s: *s,
+                                 f: *f,
+                                 c: self.localize_head(c, from_slabref, false), }
+            },
+            MemoBody::ContextExchange(heads) => {
+                MemoBody::ContextExchange(heads.iter().map(|head| self.localize_head(head, from_slabref, false)).collect())
+            },
+            MemoBody::ContextSubscribe => MemoBody::ContextSubscribe,
         }
     }
 
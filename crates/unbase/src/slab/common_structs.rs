@@ -13,13 +13,29 @@ use crate::{
     slab::SlabId,
 };
 use itertools::Itertools;
+use sha2::{
+    Digest,
+    Sha256,
+};
 
 pub const MAX_SLOTS: usize = 256;
+
+/// Identifies a user-defined entity type. See [`Schema`](crate::schema::Schema)
+pub type TypeId = u32;
+
+/// Entity ids for schema entities are carved out of the keyspace of this (otherwise unused) slab id, such that they may
+/// be located in the root index by type id alone
+const SCHEMA_SLAB_ID: SlabId = SlabId::MAX;
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 
 pub enum EntityType {
     IndexNode,
     Record,
+    /// Describes a user-defined type. Stored under a deterministic EntityId - see `EntityId::schema`
+    Schema,
+    /// A user-defined type, the writes for which are validated against its registered Schema
+    Custom(TypeId),
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
@@ -46,16 +62,104 @@ impl EntityId {
                    stype: EntityType::IndexNode, }
     }
 
-    /// Human readable version of the EntityID which denotes whether the entity is an (I)ndex or a (R)ecord type
+    /// The deterministic EntityId of the Schema entity for a given user-defined type
+    pub fn schema(type_id: TypeId) -> Self {
+        EntityId { id:    (SCHEMA_SLAB_ID as u64).rotate_left(32) | type_id as u64,
+                   stype: EntityType::Schema, }
+    }
+
+    /// Derive a Record EntityId from a natural key within a namespace. Entities created independently for the same key
+    /// on different slabs are thus one and the same, rather than duplicates
+    pub fn from_natural_key(namespace: &str, key: &str) -> Self {
+        EntityId { id:    hash_id(&[namespace.as_bytes(), key.as_bytes()]),
+                   stype: EntityType::Record, }
+    }
+
+    /// The deterministic EntityId of the index node beneath the given slot of another index node
+    pub(crate) fn index_child(parent: EntityId, slot_id: SlotId) -> Self {
+        EntityId { id:    hash_id(&[&parent.id.to_be_bytes(), &[slot_id]]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the root node of the secondary index for a given field
+    pub fn secondary_index(field: &str) -> Self {
+        EntityId { id:    hash_id(&[format!("index:{}", field).as_bytes()]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the root node of the full-text index for a given field
+    pub fn fulltext_index(field: &str) -> Self {
+        EntityId { id:    hash_id(&[format!("fulltext:{}", field).as_bytes()]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the root node of the ordered index for a given field
+    pub fn ordered_index(field: &str) -> Self {
+        EntityId { id:    hash_id(&[format!("ordered:{}", field).as_bytes()]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the node beneath which a secondary index lists the entities having values with the
+    /// given key
+    pub(crate) fn index_posting(index: EntityId, value_key: u64) -> Self {
+        EntityId { id:    hash_id(&[&index.id.to_be_bytes(), b"posting", &value_key.to_be_bytes()]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the root node of the index which lists the entities of a user-defined type
+    pub fn type_index(type_id: TypeId) -> Self {
+        EntityId { id:    hash_id(&[b"type", &type_id.to_be_bytes()]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the root node of the composite index over the given fields, in any order
+    pub fn composite_index(fields: &[&str]) -> Self {
+        let mut parts: Vec<&[u8]> = fields.iter().map(|field| field.as_bytes()).collect();
+        parts.sort_unstable();
+        parts.insert(0, b"composite");
+
+        EntityId { id:    hash_id(&parts),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the node which lists the secondary, full-text and composite indexes which have been
+    /// declared
+    pub(crate) fn index_catalog() -> Self {
+        EntityId { id:    hash_id(&[b"index-catalog"]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// The deterministic EntityId of the node through which a covering index leads to an entity, and which carries
+    /// copies of the covered values of that entity
+    pub(crate) fn index_cover(index: EntityId, entity_id: EntityId) -> Self {
+        EntityId { id:    hash_id(&[&index.id.to_be_bytes(), b"cover", &entity_id.id.to_be_bytes()]),
+                   stype: EntityType::IndexNode, }
+    }
+
+    /// Human readable version of the EntityID which denotes whether the entity is an (I)ndex, a (R)ecord, a (S)chema,
+    /// or a user-defined (T)ype
     pub fn concise_string(&self) -> String {
         use self::EntityType::*;
         match self.stype {
             IndexNode => format!("I{}", self.id),
             Record => format!("R{}", self.id),
+            Schema => format!("S{}", self.id),
+            Custom(type_id) => format!("T{}.{}", type_id, self.id),
         }
     }
 }
 
+/// Hash a sequence of length-prefixed byte strings into an id
+pub(crate) fn hash_id(parts: &[&[u8]]) -> u64 {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.input((part.len() as u64).to_be_bytes());
+        hasher.input(part);
+    }
+
+    hasher.result().iter().take(8).fold(0u64, |id, byte| id << 8 | *byte as u64)
+}
+
 impl fmt::Display for EntityId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}-{}", self.stype, self.id)
@@ -198,6 +302,14 @@ pub enum EdgeLink {
     Vacant { slot_id: SlotId },
     Occupied { slot_id: SlotId, head: Head },
 }
+
+impl EdgeLink {
+    pub fn slot_id(&self) -> SlotId {
+        match *self {
+            EdgeLink::Vacant { slot_id } | EdgeLink::Occupied { slot_id, .. } => slot_id,
+        }
+    }
+}
 // TODO: consider making this a Vec
 #[derive(Clone, Debug, Default)]
 pub struct EdgeSet(pub HashMap<SlotId, Head>);
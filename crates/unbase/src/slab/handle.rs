@@ -1,12 +1,16 @@
 use futures::{
     channel::mpsc,
     future::{
+        join_all,
         select,
         Either,
     },
 };
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
 use tracing::trace;
 
 use crate::{
@@ -28,6 +32,7 @@ use crate::{
         MemoId,
         MemoRef,
         SlabAnticipatedLifetime,
+        SlabId,
         SlabPresence,
     },
     Network,
@@ -39,6 +44,9 @@ use std::time::{
 };
 use timer::Delay;
 
+/// How long to wait for memos which are fetched ahead of their being needed. See `SlabHandle::fetch_memos`
+pub const PREFETCH_TIMEOUT: Duration = Duration::from_millis(1000);
+
 // TODO change this to
 // pub struct SlabHandle(Arc<SlabHandleInner>);
 
@@ -114,6 +122,37 @@ impl SlabHandle {
         Err(RetrieveError::NotFoundByDeadline)
     }
 
+    /// Request those of the given memos which are not yet resident, sending each peer a single request for all of the
+    /// memos which it is known to hold, and wait no longer than the timeout for them to arrive. Returns true if they
+    /// are all resident. Callers fetching ahead of need may use `PREFETCH_TIMEOUT`, and leave any memos which have
+    /// yet to arrive to be retrieved as they are visited.
+    pub async fn fetch_memos(&self, memorefs: &[MemoRef], timeout: Duration) -> bool {
+        let mut requests: HashMap<SlabId, (SlabRef, Vec<MemoId>)> = HashMap::new();
+        let mut channels = Vec::new();
+
+        for memoref in memorefs.iter().filter(|memoref| !memoref.is_resident()) {
+            channels.push(self.agent.memo_wait_channel(memoref.id));
+
+            for peer in memoref.peerlist.read().unwrap().iter().take(5) {
+                requests.entry(peer.slabref.slab_id)
+                        .or_insert_with(|| (peer.slabref.clone(), Vec::new()))
+                        .1
+                        .push(memoref.id);
+            }
+        }
+
+        if !requests.is_empty() {
+            for (_, (slabref, memo_ids)) in requests {
+                let request_memo = self.new_memo(None, Head::Null, MemoBody::MemoRequest(memo_ids, self.my_ref.clone()));
+                slabref.send(&self.my_ref, &request_memo);
+            }
+
+            select(join_all(channels), Delay::new(timeout)).await;
+        }
+
+        memorefs.iter().all(|memoref| memoref.is_resident())
+    }
+
     #[tracing::instrument]
     pub fn new_memo(&self, entity_id: Option<EntityId>, parents: Head, body: MemoBody) -> MemoRef {
         self.agent.new_memo(entity_id, parents, body)
@@ -124,6 +163,18 @@ impl SlabHandle {
         self.agent.new_memo(entity_id, Head::Null, body)
     }
 
+    pub fn new_chunk_memo(&self, data: Vec<u8>) -> MemoRef {
+        self.agent.new_chunk_memo(data)
+    }
+
+    pub fn new_chunk_tree_memo(&self, children: Vec<MemoRef>) -> MemoRef {
+        self.agent.new_chunk_tree_memo(children)
+    }
+
+    pub fn new_genesis_memo(&self, entity_id: EntityId, body: MemoBody) -> MemoRef {
+        self.agent.new_genesis_memo(entity_id, body)
+    }
+
     pub fn generate_entity_id(&self, stype: EntityType) -> EntityId {
         self.agent.generate_entity_id(stype)
     }
@@ -21,6 +21,7 @@ use crate::{
         SlabRef,
     },
     slab::{
+        hash_id,
         EdgeSet,
         EntityId,
         EntityType,
@@ -80,6 +81,51 @@ pub enum MemoBody {
     },
     Peering(MemoId, Option<EntityId>, MemoPeerList),
     MemoRequest(Vec<MemoId>, SlabRef),
+    /// A content-addressed chunk of binary data. See `chunk_memo_id`
+    Chunk(Vec<u8>),
+    /// A content-addressed node in the tree of chunks of a blob. Its memorefs are, in order, either the Chunk memos of
+    /// the blob or the ChunkTree memos of the tier beneath. See `chunk_tree_memo_id`
+    ChunkTree(Head),
+    /// Binds a binary blob of length l to key k of the entity. Each chunk is s bytes long, except perhaps the last.
+    /// The memorefs of c are the top tier of a tree with fanout f, whose leaves are the chunks of the blob, in order.
+    /// The tree has as many tiers as are needed for no node to have more than f memorefs, so that no memo grows
+    /// with the size of the blob.
+    Blob {
+        k: String,
+        l: u64,
+        s: u32,
+        f: u32,
+        c: Head,
+    },
+    /// The heads of the index nodes in the stash of a context on another slab, sent by `Context::send_context`
+    ContextExchange(Vec<Head>),
+    /// Asks the recipient to send us its context exchanges, as we have contexts of our own to apply them to
+    ContextSubscribe,
+}
+
+/// Memo ids which are derived from content have the high bit set. Counter-based memo ids carry the slab id in their
+/// upper half, and slab ids never exceed `MAX_SLAB_ID`, so the two cannot collide. Each kind of derived id hashes a
+/// distinct tag, such that they cannot collide with one another either.
+fn derived_memo_id(tag: &[u8], content: &[u8]) -> MemoId {
+    hash_id(&[tag, content]) | 1 << 63
+}
+
+/// Chunk memos are identified by the hash of their contents, such that identical chunks are stored only once.
+pub fn chunk_memo_id(data: &[u8]) -> MemoId {
+    derived_memo_id(b"chunk", data)
+}
+
+/// ChunkTree memos are identified by the ids of the memos beneath them, such that identical blobs share their trees.
+pub fn chunk_tree_memo_id(children: &[MemoRef]) -> MemoId {
+    let ids: Vec<u8> = children.iter().flat_map(|memoref| memoref.id.to_be_bytes()).collect();
+
+    derived_memo_id(b"chunk_tree", &ids)
+}
+
+/// The genesis memo of an entity with a deterministic id is itself identified deterministically, such that concurrent
+/// creations of that entity on different slabs yield the same memo.
+pub fn genesis_memo_id(entity_id: EntityId) -> MemoId {
+    derived_memo_id(b"genesis", entity_id.to_string().as_bytes())
 }
 
 // use std::hash::{Hash, Hasher};
@@ -138,6 +184,8 @@ impl Memo {
     pub fn does_peering(&self) -> bool {
         match self.body {
             MemoBody::MemoRequest(_, _) => false,
+            MemoBody::ContextExchange(_) => false,
+            MemoBody::ContextSubscribe => false,
             MemoBody::Peering(_, _, _) => false,
             MemoBody::SlabPresence { p: _, r: _ } => false,
             _ => true,
@@ -191,6 +239,17 @@ impl MemoBody {
             MemoRequest(ref memo_ids, ref slabref) => {
                 format!("MemoRequest({} to {})", memo_ids.iter().join(","), slabref.slab_id)
             },
+            Chunk(ref data) => format!("Chunk({} bytes)", data.len()),
+            ChunkTree(ref head) => format!("ChunkTree({} memos)", head.len()),
+            Blob { ref k, ref l, .. } => format!("Blob({}, {} bytes)", k, l),
+            ContextExchange(ref heads) => format!("ContextExchange({} heads)", heads.len()),
+            ContextSubscribe => "ContextSubscribe".to_string(),
         }
     }
+
+    /// Transient memos concern only the moment they are sent, so they are neither stored nor peered, by the sender or
+    /// the recipient
+    pub fn is_transient(&self) -> bool {
+        matches!(self, MemoBody::ContextExchange(_) | MemoBody::ContextSubscribe)
+    }
 }
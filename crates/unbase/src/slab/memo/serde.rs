@@ -34,6 +34,10 @@ struct MBFullyMaterializedSeed<'a> {
     dest_slab:      &'a SlabHandle,
     origin_slabref: &'a SlabRef,
 }
+struct MBBlobSeed<'a> {
+    dest_slab:      &'a SlabHandle,
+    origin_slabref: &'a SlabRef,
+}
 // TODO convert this to a non-seed deserializer
 struct MBPeeringSeed<'a> {
     dest_slab: &'a SlabHandle,
@@ -117,6 +121,27 @@ impl StatefulSerialize for MemoBody {
                 sv.serialize_field("s", &SerializeWrapper(slabref, helper))?;
                 sv.end()
             },
+            Chunk(ref data) => serializer.serialize_newtype_variant("MemoBody", 8, "Chunk", data),
+            Blob { ref k,
+                   ref l,
+                   ref s,
+                   ref f,
+                   ref c, } => {
+                let mut sv = serializer.serialize_struct_variant("MemoBody", 9, "Blob", 5)?;
+                sv.serialize_field("k", k)?;
+                sv.serialize_field("l", l)?;
+                sv.serialize_field("s", s)?;
+                sv.serialize_field("f", f)?;
+                sv.serialize_field("c", &SerializeWrapper(c, helper))?;
+                sv.end()
+            },
+            ContextExchange(ref heads) => {
+                serializer.serialize_newtype_variant("MemoBody", 10, "ContextExchange", &SerializeWrapper(heads, helper))
+            },
+            ContextSubscribe => serializer.serialize_unit_variant("MemoBody", 11, "ContextSubscribe"),
+            ChunkTree(ref head) => {
+                serializer.serialize_newtype_variant("MemoBody", 12, "ChunkTree", &SerializeWrapper(head, helper))
+            },
         }
     }
 }
@@ -218,6 +243,11 @@ enum MBVariant {
     PartiallyMaterialized,
     Peering,
     MemoRequest,
+    Chunk,
+    Blob,
+    ContextExchange,
+    ContextSubscribe,
+    ChunkTree,
 }
 
 impl<'a> DeserializeSeed for MemoBodySeed<'a> {
@@ -233,7 +263,12 @@ impl<'a> DeserializeSeed for MemoBodySeed<'a> {
                                                              "FullyMaterialized",
                                                              "PartiallyMaterialized",
                                                              "Peering",
-                                                             "MemoRequest"];
+                                                             "MemoRequest",
+                                                             "Chunk",
+                                                             "Blob",
+                                                             "ContextExchange",
+                                                             "ContextSubscribe",
+                                                             "ChunkTree"];
 
         deserializer.deserialize_enum("MemoBody", MEMOBODY_VARIANTS, self)
     }
@@ -275,6 +310,22 @@ impl<'a> Visitor for MemoBodySeed<'a> {
                 variant.visit_newtype_seed(MBMemoRequestSeed { dest_slab:      self.dest_slab,
                                                                origin_slabref: self.origin_slabref, })
             },
+            (MBVariant::Chunk, variant) => variant.visit_newtype().map(MemoBody::Chunk),
+            (MBVariant::Blob, variant) => {
+                variant.visit_newtype_seed(MBBlobSeed { dest_slab:      self.dest_slab,
+                                                        origin_slabref: self.origin_slabref, })
+            },
+            (MBVariant::ContextExchange, variant) => {
+                variant.visit_newtype_seed(VecSeed(HeadSeed { dest_slab:      self.dest_slab,
+                                                              origin_slabref: self.origin_slabref, }))
+                       .map(MemoBody::ContextExchange)
+            },
+            (MBVariant::ContextSubscribe, variant) => variant.visit_unit().map(|()| MemoBody::ContextSubscribe),
+            (MBVariant::ChunkTree, variant) => {
+                variant.visit_newtype_seed(HeadSeed { dest_slab:      self.dest_slab,
+                                                      origin_slabref: self.origin_slabref, })
+                       .map(MemoBody::ChunkTree)
+            },
             _ => unimplemented!(),
         }
     }
@@ -527,3 +578,48 @@ impl<'a> Visitor for MBPeeringSeed<'a> {
         }
     }
 }
+
+impl<'a> DeserializeSeed for MBBlobSeed<'a> {
+    type Value = MemoBody;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize(self)
+    }
+}
+impl<'a> Visitor for MBBlobSeed<'a> {
+    type Value = MemoBody;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("MemoBody::Blob")
+    }
+
+    fn visit_map<Visitor>(self, mut visitor: Visitor) -> Result<Self::Value, Visitor::Error>
+        where Visitor: MapVisitor
+    {
+        let mut key = None;
+        let mut len = None;
+        let mut chunk_size = None;
+        let mut fanout = None;
+        let mut chunks = None;
+        while let Some(k) = visitor.visit_key()? {
+            match k {
+                'k' => key = visitor.visit_value()?,
+                'l' => len = visitor.visit_value()?,
+                's' => chunk_size = visitor.visit_value()?,
+                'f' => fanout = visitor.visit_value()?,
+                'c' => {
+                    chunks = Some(visitor.visit_value_seed(HeadSeed { dest_slab:      self.dest_slab,
+                                                                      origin_slabref: self.origin_slabref, })?)
+                },
+                _ => {},
+            }
+        }
+
+        match (key, len, chunk_size, fanout, chunks) {
+            (Some(k), Some(l), Some(s), Some(f), Some(c)) => Ok(MemoBody::Blob { k, l, s, f, c }),
+            _ => Err(DeError::invalid_length(0, &self)),
+        }
+    }
+}
@@ -138,6 +138,10 @@ impl<'a> Visitor for MemoRefSeed<'a> {
                                   MemoPeeringStatus::Participating
                               }, });
 
+        // A slab is never its own peer. This only arises for memorefs which were not serialized for us in particular,
+        // such as those in a saved context
+        peers.retain(|peer| peer.slabref.slab_id != self.dest_slab.my_ref.slab_id);
+
         Ok(self.dest_slab
                .agent
                .assert_memoref(memo_id, entity_id, MemoPeerList::new(peers), None)
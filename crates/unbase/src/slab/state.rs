@@ -26,6 +26,8 @@ pub(super) struct SlabState {
     pub memo_wait_channels:   HashMap<MemoId, Vec<oneshot::Sender<Memo>>>,
     pub entity_subscriptions: HashMap<EntityId, Vec<mpsc::Sender<Head>>>,
     pub index_subscriptions:  Vec<mpsc::Sender<Head>>,
+    /// The peer slabs which have asked for our context exchanges
+    pub exchange_subscribers: Vec<SlabRef>,
     pub running:              bool,
 }
 
@@ -51,6 +53,7 @@ impl SlabState {
                     memo_wait_channels:   HashMap::new(),
                     entity_subscriptions: HashMap::new(),
                     index_subscriptions:  Vec::new(),
+                    exchange_subscribers: Vec::new(),
                     running:              true, }
     }
 }
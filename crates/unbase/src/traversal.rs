@@ -0,0 +1,301 @@
+//! Breadth- and depth-first walks over the graph formed by the relations and edges of entities.
+//!
+//! ```
+//! # use unbase::{Network, Slab, Entity};
+//! # use futures::TryStreamExt;
+//! # async_std::task::block_on(async {
+//! # let net = Network::create_new_system();
+//! # let slab = Slab::new(&net);
+//! # let context = slab.create_context();
+//! let mut alice = Entity::new_with_single_kv(&context, "name", "Alice").await.unwrap();
+//! let bob = Entity::new_with_single_kv(&context, "name", "Bob").await.unwrap();
+//! alice.set_relation(0, &bob).await.unwrap();
+//!
+//! let reached: Vec<_> = context.traverse(&alice).max_depth(2).stream().try_collect().await.unwrap();
+//! assert_eq!(reached.len(), 2);
+//! assert_eq!(reached[1].0.end(), bob.id);
+//! # });
+//! ```
+
+use crate::{
+    context::Context,
+    entity::Entity,
+    error::RetrieveError,
+    slab::{
+        EdgeLink,
+        EntityId,
+        MemoRef,
+        SlotId,
+        PREFETCH_TIMEOUT,
+    },
+};
+
+use futures::stream::{
+    self,
+    BoxStream,
+    StreamExt,
+};
+use std::{
+    collections::{
+        HashSet,
+        VecDeque,
+    },
+    fmt,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    BreadthFirst,
+    DepthFirst,
+}
+
+/// The kinds of link between entities
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Link {
+    /// Set via [`Entity::set_relation`]
+    Relation,
+    /// Set via [`Head::set_edge`](crate::head::Head::set_edge)
+    Edge,
+}
+
+/// A single step of a [`Path`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hop {
+    pub link:      Link,
+    pub slot_id:   SlotId,
+    pub entity_id: EntityId,
+}
+
+/// The route by which a traversal reached an entity
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    pub start: EntityId,
+    pub hops:  Vec<Hop>,
+}
+
+impl Path {
+    /// The number of hops from the starting entity
+    pub fn depth(&self) -> usize {
+        self.hops.len()
+    }
+
+    /// The entity at the end of the path
+    pub fn end(&self) -> EntityId {
+        self.hops.last().map_or(self.start, |hop| hop.entity_id)
+    }
+
+    fn extend(&self, hop: Hop) -> Path {
+        let mut hops = self.hops.clone();
+        hops.push(hop);
+
+        Path { start: self.start,
+               hops }
+    }
+}
+
+/// A walk over the graph reachable from a starting entity. Each entity is visited at most once, so cycles are not
+/// followed, and the starting entity is yielded first along with an empty path.
+#[derive(Clone)]
+pub struct Traversal {
+    context:   Context,
+    start:     Entity,
+    order:     Order,
+    max_depth: Option<usize>,
+    slots:     Option<Vec<SlotId>>,
+    links:     Option<Link>,
+}
+
+impl Traversal {
+    pub fn new(context: &Context, start: &Entity) -> Self {
+        Traversal { context:   context.clone(),
+                    start:     start.clone(),
+                    order:     Order::BreadthFirst,
+                    max_depth: None,
+                    slots:     None,
+                    links:     None, }
+    }
+
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn breadth_first(self) -> Self {
+        self.order(Order::BreadthFirst)
+    }
+
+    pub fn depth_first(self) -> Self {
+        self.order(Order::DepthFirst)
+    }
+
+    /// Don't follow links from entities which are this many hops from the start
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Only follow links in the given slots
+    pub fn slots(mut self, slots: &[SlotId]) -> Self {
+        self.slots = Some(slots.to_vec());
+        self
+    }
+
+    /// Only follow links of the given kind. Otherwise both relations and edges are followed.
+    pub fn only(mut self, link: Link) -> Self {
+        self.links = Some(link);
+        self
+    }
+
+    /// Execute the traversal, yielding each entity reached along with the path by which it was first reached. With
+    /// breadth-first order, that is a shortest path.
+    pub fn stream(self) -> BoxStream<'static, Result<(Path, Entity), RetrieveError>> {
+        let start = Path { start: self.start.id,
+                           hops:  Vec::new(), };
+
+        let mut pending = VecDeque::new();
+        pending.push_back((start, self.start.clone()));
+
+        let walk = Walk { traversal: self,
+                          pending,
+                          visited: HashSet::new() };
+
+        stream::unfold(Some(walk), |walk| {
+            async move {
+                let mut walk = walk?;
+
+                match walk.next().await {
+                    Ok(Some(item)) => Some((Ok(item), Some(walk))),
+                    Ok(None) => None,
+                    // Stop at the first error
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        }).boxed()
+    }
+}
+
+impl fmt::Debug for Traversal {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Traversal")
+           .field("start", &self.start.id)
+           .field("order", &self.order)
+           .field("max_depth", &self.max_depth)
+           .field("slots", &self.slots)
+           .field("links", &self.links)
+           .finish()
+    }
+}
+
+struct Walk {
+    traversal: Traversal,
+    pending:   VecDeque<(Path, Entity)>,
+    visited:   HashSet<EntityId>,
+}
+
+impl Walk {
+    async fn next(&mut self) -> Result<Option<(Path, Entity)>, RetrieveError> {
+        loop {
+            let next = match self.traversal.order {
+                Order::BreadthFirst => self.pending.pop_front(),
+                Order::DepthFirst => self.pending.pop_back(),
+            };
+
+            let (path, mut entity) = match next {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+
+            // An entity may be pending via several paths, but is only visited via the first
+            if !self.visited.insert(entity.id) {
+                continue;
+            }
+
+            if self.traversal.max_depth.is_none_or(|max_depth| path.depth() < max_depth) {
+                let mut neighbours = self.neighbours(&path, &mut entity).await?;
+
+                // Depth first pops from the back, so push in reverse to visit the lowest slot first
+                if self.traversal.order == Order::DepthFirst {
+                    neighbours.reverse();
+                }
+                self.pending.extend(neighbours);
+            }
+
+            return Ok(Some((path, entity)));
+        }
+    }
+
+    fn follows(&self, link: Link, slot_id: SlotId) -> bool {
+        self.traversal.links.is_none_or(|only| only == link)
+        && self.traversal.slots.as_ref().is_none_or(|slots| slots.contains(&slot_id))
+    }
+
+    /// The unvisited entities linked from the given one, in slot order
+    async fn neighbours(&self, path: &Path, entity: &mut Entity) -> Result<Vec<(Path, Entity)>, RetrieveError> {
+        let context = &self.traversal.context;
+        let slab = &context.slab;
+
+        context.mut_update_record_head_for_consistency(&mut entity.head).await?;
+
+        let mut links: Vec<(Link, SlotId, Entity)> = Vec::new();
+
+        let mut relations: Vec<(SlotId, EntityId)> = entity.head
+                                                           .project_relations(slab)
+                                                           .await?
+                                                           .0
+                                                           .into_iter()
+                                                           .filter_map(|(slot_id, entity_id)| Some((slot_id, entity_id?)))
+                                                           .filter(|(slot_id, _)| self.follows(Link::Relation, *slot_id))
+                                                           .collect();
+        relations.sort();
+
+        relations.retain(|(_, entity_id)| !self.visited.contains(entity_id));
+
+        // Resolve the related entities in a single walk of the root index
+        let entity_ids: Vec<EntityId> = relations.iter().map(|(_, entity_id)| *entity_id).collect();
+        for ((slot_id, _), related) in relations.into_iter().zip(context.get_entities(&entity_ids).await?) {
+            if let Some(related) = related {
+                links.push((Link::Relation, slot_id, related));
+            }
+        }
+
+        if self.traversal.links.is_none_or(|only| only == Link::Edge) {
+            let mut edges = entity.head.project_occupied_edges(slab).await?;
+            edges.sort_by_key(|edgelink| edgelink.slot_id());
+
+            for edgelink in edges {
+                if let EdgeLink::Occupied { slot_id, head } = edgelink {
+                    if !self.follows(Link::Edge, slot_id) {
+                        continue;
+                    }
+                    match head.entity_id() {
+                        Some(entity_id) if !self.visited.contains(&entity_id) => {
+                            links.push((Link::Edge, slot_id, context.get_entity_from_head(head).await?));
+                        },
+                        _ => {},
+                    }
+                }
+            }
+        }
+
+        // Fetch the heads of all the neighbours at once, rather than as each is visited. Any which don't arrive in time
+        // are retrieved when their entity is visited
+        let memorefs: Vec<MemoRef> = links.iter().flat_map(|(_, _, entity)| entity.head.iter().cloned()).collect();
+        slab.fetch_memos(&memorefs, PREFETCH_TIMEOUT).await;
+
+        Ok(links.into_iter()
+                .map(|(link, slot_id, entity)| {
+                    let hop = Hop { link,
+                                    slot_id,
+                                    entity_id: entity.id };
+                    (path.extend(hop), entity)
+                })
+                .collect())
+    }
+}
+
+impl Context {
+    /// Begin a [`Traversal`] of the graph reachable from the given entity
+    pub fn traverse(&self, start: &Entity) -> Traversal {
+        Traversal::new(self, start)
+    }
+}
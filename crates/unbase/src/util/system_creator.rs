@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     head::Head,
+    index::IndexShape,
     slab::{
         EdgeSet,
         EntityType,
@@ -14,9 +15,10 @@ use crate::{
 pub struct SystemCreator;
 
 impl SystemCreator {
-    pub fn generate_root_index_seed(slab: &SlabHandle) -> Head {
+    pub fn generate_root_index_seed(slab: &SlabHandle, shape: IndexShape) -> Head {
         let mut values = HashMap::new();
         values.insert("tier".to_string(), 0.to_string());
+        shape.to_values(&mut values);
 
         let memoref = slab.new_memo_noparent(Some(slab.generate_entity_id(EntityType::IndexNode)),
                                              MemoBody::FullyMaterialized { v: values,
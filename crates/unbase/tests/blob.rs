@@ -0,0 +1,195 @@
+use unbase::{
+    blob::{
+        CHUNK_FANOUT,
+        CHUNK_SIZE,
+    },
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+use futures::io::{
+    AsyncReadExt,
+    AsyncSeekExt,
+    SeekFrom,
+};
+
+#[unbase_test_util::async_test]
+async fn blob_roundtrip() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let data: Vec<u8> = (0..(CHUNK_SIZE * 3 + 100)).map(|i| (i % 251) as u8).collect();
+
+    let mut record = Entity::new_with_single_kv(&context_a, "name", "photo").await.unwrap();
+    record.set_blob("image", &data).await.unwrap();
+    record.set_value("caption", "A tiger").await.unwrap();
+
+    let mut reader = record.get_blob("image").await.unwrap().expect("blob");
+    assert_eq!(reader.len(), data.len() as u64);
+
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).await.unwrap();
+    assert_eq!(read, data);
+
+    // Partial read spanning a chunk boundary
+    reader.seek(SeekFrom::Start(CHUNK_SIZE as u64 - 10)).await.unwrap();
+    let mut buf = [0u8; 20];
+    reader.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf[..], &data[CHUNK_SIZE - 10..CHUNK_SIZE + 10]);
+
+    reader.seek(SeekFrom::End(-5)).await.unwrap();
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail).await.unwrap();
+    assert_eq!(&tail[..], &data[data.len() - 5..]);
+
+    assert!(record.get_blob("missing").await.unwrap().is_none());
+    assert_eq!(record.get_value("caption").await.unwrap(), Some("A tiger".to_string()));
+}
+
+#[unbase_test_util::async_test]
+async fn blob_chunk_dedup() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let mut record = Entity::new_blank(&context_a).await.unwrap();
+
+    // Three identical chunks are stored once
+    let before = slab_a.count_of_memorefs_resident();
+    record.set_blob("zeros", &vec![0u8; CHUNK_SIZE * 3]).await.unwrap();
+    let zeros_added = slab_a.count_of_memorefs_resident() - before;
+
+    // As is an identical blob under another key
+    let before = slab_a.count_of_memorefs_resident();
+    record.set_blob("more_zeros", &vec![0u8; CHUNK_SIZE * 3]).await.unwrap();
+    assert_eq!(slab_a.count_of_memorefs_resident() - before, zeros_added - 1);
+
+    // Whereas three distinct chunks are stored separately
+    let before = slab_a.count_of_memorefs_resident();
+    let distinct: Vec<u8> = (0..CHUNK_SIZE * 3).map(|i| (i / CHUNK_SIZE) as u8 + 1).collect();
+    record.set_blob("distinct", &distinct).await.unwrap();
+    assert_eq!(slab_a.count_of_memorefs_resident() - before, zeros_added + 2);
+}
+
+#[unbase_test_util::async_test]
+async fn blob_remote() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let data: Vec<u8> = (0..(CHUNK_SIZE * 2 + 1)).map(|i| (i % 7) as u8).collect();
+
+    let mut record = Entity::new_with_single_kv(&context_a, "name", "archive").await.unwrap();
+    record.set_blob("contents", &data).await.unwrap();
+
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    let mut remote = context_b.get_entity_by_id(record.id).await.unwrap().expect("found");
+    let mut reader = remote.get_blob("contents").await.unwrap().expect("blob");
+
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).await.unwrap();
+    assert_eq!(read, data);
+
+    simulator.quiesce_and_stop().await;
+}
+
+/// Enough distinct chunks to need two tiers of ChunkTree memos beneath the Blob memo
+fn two_tier_blob() -> Vec<u8> {
+    (0..CHUNK_FANOUT * CHUNK_FANOUT + 2).flat_map(|chunk| {
+                                            let mut data: Vec<u8> = (0..CHUNK_SIZE).map(|i| (i % 251) as u8).collect();
+                                            data[..4].copy_from_slice(&(chunk as u32).to_be_bytes());
+                                            data
+                                        })
+                                        .collect()
+}
+
+#[unbase_test_util::async_test]
+async fn blob_chunk_tree() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let data = two_tier_blob();
+    let chunk_count = data.len() / CHUNK_SIZE;
+
+    let mut record = Entity::new_blank(&context_a).await.unwrap();
+
+    // A blob of a single chunk needs no ChunkTree memos
+    let before = slab_a.count_of_memorefs_resident();
+    record.set_blob("thumbnail", b"small").await.unwrap();
+    let single_chunk_added = (slab_a.count_of_memorefs_resident() - before) as usize;
+
+    // Whereas this one adds the lower tier of nodes and the upper tier of two nodes, besides its chunks
+    let before = slab_a.count_of_memorefs_resident();
+    record.set_blob("video", &data).await.unwrap();
+    let lower_tier = chunk_count.div_ceil(CHUNK_FANOUT);
+    let upper_tier = lower_tier.div_ceil(CHUNK_FANOUT);
+    assert_eq!(upper_tier, 2);
+    assert_eq!((slab_a.count_of_memorefs_resident() - before) as usize,
+               single_chunk_added - 1 + chunk_count + lower_tier + upper_tier);
+
+    let mut reader = record.get_blob("video").await.unwrap().expect("blob");
+    let mut read = Vec::new();
+    reader.read_to_end(&mut read).await.unwrap();
+    assert!(read == data);
+}
+
+#[unbase_test_util::async_test]
+async fn blob_chunk_tree_remote() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let data = two_tier_blob();
+    let chunk_count = data.len() / CHUNK_SIZE;
+
+    let mut record = Entity::new_blank(&context_a).await.unwrap();
+    record.set_blob("video", &data).await.unwrap();
+
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    let mut remote = context_b.get_entity_by_id(record.id).await.unwrap().expect("found");
+    let mut reader = remote.get_blob("video").await.unwrap().expect("blob");
+    assert_eq!(reader.len(), data.len() as u64);
+
+    // Either side of the boundaries between nodes of each tier
+    for chunk in [0, CHUNK_FANOUT - 1, CHUNK_FANOUT, CHUNK_FANOUT * CHUNK_FANOUT, chunk_count - 1].iter() {
+        let position = chunk * CHUNK_SIZE + 10;
+        reader.seek(SeekFrom::Start(position as u64)).await.unwrap();
+        let mut buf = [0u8; 20];
+        reader.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf[..], &data[position..position + 20]);
+    }
+
+    simulator.quiesce_and_stop().await;
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+use unbase::{
+    context::CausalToken,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn read_your_writes() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    // The session writes via slab A
+    let context_a = slab_a.create_context();
+    let mut cow = Entity::new_with_single_kv(&context_a, "animal_sound", "Moo").await.unwrap();
+    cow.set_value("animal_sound", "Moooo").await.unwrap();
+
+    let header = context_a.causal_token().await.unwrap().to_string();
+    simulator.quiesce().await;
+
+    // ...and then reads via slab B, which knows nothing of the write without the token
+    let context_b = slab_b.create_context();
+    assert!(context_b.get_entity(cow.id).await.unwrap().is_none());
+
+    let token: CausalToken = header.parse().unwrap();
+    // The stash is compacted before the token is issued, so only the root index need be listed
+    assert_eq!(token.len(), 1);
+    context_b.await_token(&token, Duration::from_secs(5)).await.unwrap();
+
+    let mut found = context_b.get_entity(cow.id).await.unwrap().expect("found after awaiting the token");
+    assert_eq!(found.get_value("animal_sound").await.unwrap(), Some("Moooo".to_string()));
+
+    // A token may be awaited more than once
+    context_b.await_token(&token, Duration::from_secs(5)).await.unwrap();
+
+    simulator.quiesce_and_stop().await;
+}
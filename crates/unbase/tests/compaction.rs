@@ -0,0 +1,121 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+use unbase::{
+    context::CompactionPolicy,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn background_compaction() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab = Slab::new(&net);
+    let context = slab.create_context();
+
+    // Nothing happens of its own accord by default
+    assert!(!context.compaction_policy().is_enabled());
+
+    let mut entity_ids = Vec::new();
+    for i in 0..20 {
+        entity_ids.push(Entity::new_with_single_kv(&context, "number", &i.to_string()).await.unwrap().id);
+    }
+    let metrics = context.compaction_metrics();
+    assert_eq!(metrics.runs, 0);
+    assert!(metrics.pending_writes > 0);
+
+    context.set_compaction_policy(CompactionPolicy::new().max_writes(10).poll(Duration::from_millis(10)));
+
+    for i in 20..40 {
+        entity_ids.push(Entity::new_with_single_kv(&context, "number", &i.to_string()).await.unwrap().id);
+    }
+
+    async_std::task::sleep(Duration::from_millis(200)).await;
+
+    let metrics = context.compaction_metrics();
+    assert!(metrics.background_runs > 0, "{:?}", metrics);
+    assert_eq!(metrics.failures, 0);
+    assert!(metrics.heads_pruned > 0);
+    assert!(metrics.pending_writes < 10);
+
+    // Nothing went missing in the process
+    for entity_id in entity_ids.iter() {
+        assert!(context.get_entity(*entity_id).await.unwrap().is_some());
+    }
+
+    // Once disabled, writes accumulate again
+    context.set_compaction_policy(CompactionPolicy::new());
+    let runs = context.compaction_metrics().runs;
+
+    for i in 40..60 {
+        Entity::new_with_single_kv(&context, "number", &i.to_string()).await.unwrap();
+    }
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    let metrics = context.compaction_metrics();
+    assert_eq!(metrics.runs, runs);
+    assert!(metrics.pending_writes >= 20);
+
+    // Foreground compactions are counted too
+    context.compact().await.unwrap();
+    assert_eq!(context.compaction_metrics().runs, runs + 1);
+}
+
+#[unbase_test_util::async_test]
+async fn interval_compaction() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab = Slab::new(&net);
+    let context = slab.create_context();
+
+    context.set_compaction_policy(CompactionPolicy::new().interval(Duration::from_millis(50))
+                                                         .poll(Duration::from_millis(10)));
+
+    // No writes, no compaction
+    async_std::task::sleep(Duration::from_millis(100)).await;
+    assert_eq!(context.compaction_metrics().runs, 0);
+
+    Entity::new_with_single_kv(&context, "name", "Tiger").await.unwrap();
+    async_std::task::sleep(Duration::from_millis(150)).await;
+
+    let metrics = context.compaction_metrics();
+    assert_eq!(metrics.background_runs, 1, "{:?}", metrics);
+    assert_eq!(metrics.pending_writes, 0);
+}
+
+#[unbase_test_util::async_test]
+async fn remote_write_compaction() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+    context_b.set_compaction_policy(CompactionPolicy::new().max_writes(5).poll(Duration::from_millis(10)));
+
+    // B writes nothing itself, but applies the index nodes it receives from A
+    for i in 0..10 {
+        Entity::new_with_single_kv(&context_a, "number", &i.to_string()).await.unwrap();
+    }
+    simulator.quiesce().await;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while context_b.compaction_metrics().background_runs == 0 {
+        assert!(Instant::now() < deadline, "{:?}", context_b.compaction_metrics());
+        async_std::task::sleep(Duration::from_millis(10)).await;
+    }
+
+    simulator.quiesce_and_stop().await;
+}
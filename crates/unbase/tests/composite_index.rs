@@ -0,0 +1,139 @@
+use futures::TryStreamExt;
+use std::collections::HashMap;
+use unbase::{
+    context::Context,
+    index::IndexComposite,
+    query::Row,
+    slab::EntityId,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+async fn order(context: &Context, customer: &str, status: &str, total: &str) -> Entity {
+    let mut vals = HashMap::new();
+    vals.insert("customer".to_string(), customer.to_string());
+    vals.insert("status".to_string(), status.to_string());
+    vals.insert("total".to_string(), total.to_string());
+
+    Entity::new(context, vals).await.unwrap()
+}
+
+async fn rows(context: &Context, customer: &str, status: &str) -> Vec<(EntityId, Option<String>)> {
+    let rows: Vec<Row> = context.query()
+                                .eq("customer", customer)
+                                .eq("status", status)
+                                .select(&["total"])
+                                .rows()
+                                .try_collect()
+                                .await
+                                .unwrap();
+
+    let mut rows: Vec<_> = rows.into_iter().map(|row| (row.entity_id, row.values.get("total").cloned())).collect();
+    rows.sort();
+    rows
+}
+
+#[unbase_test_util::async_test]
+async fn composite_index() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let first = order(&context_a, "alice", "open", "10").await;
+    order(&context_a, "alice", "shipped", "20").await;
+    order(&context_a, "bob", "open", "30").await;
+
+    context_a.declare_composite_index(&["status", "customer"], &[]).await.unwrap();
+    let index = IndexComposite::open(&context_a, &["customer", "status"]).await.unwrap().expect("declared");
+    assert_eq!(index.fields(), &["customer".to_string(), "status".to_string()]);
+    assert!(!index.is_covering());
+
+    let mut values = HashMap::new();
+    values.insert("customer".to_string(), "alice".to_string());
+    values.insert("status".to_string(), "open".to_string());
+    let heads = index.get(&context_a, &values).await.unwrap();
+    assert_eq!(heads.iter().map(|head| head.entity_id().unwrap()).collect::<Vec<_>>(), vec![first.id]);
+
+    let second = order(&context_a, "alice", "open", "40").await;
+    assert_eq!(rows(&context_a, "alice", "open").await,
+               vec![(first.id, Some("10".to_string())), (second.id, Some("40".to_string()))]);
+    assert_eq!(rows(&context_a, "bob", "shipped").await, vec![]);
+}
+
+#[unbase_test_util::async_test]
+async fn covering_index() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let mut first = order(&context_a, "alice", "open", "10").await;
+    context_a.declare_composite_index(&["customer", "status"], &["total"]).await.unwrap();
+    let second = order(&context_a, "alice", "open", "20").await;
+    let mut third = order(&context_a, "alice", "shipped", "30").await;
+
+    assert_eq!(rows(&context_a, "alice", "open").await,
+               vec![(first.id, Some("10".to_string())), (second.id, Some("20".to_string()))]);
+
+    // Changes to covered values and indexed values are both reflected in the index
+    first.set_value("total", "15").await.unwrap();
+    third.set_value("status", "open").await.unwrap();
+    assert_eq!(rows(&context_a, "alice", "open").await,
+               vec![(first.id, Some("15".to_string())),
+                    (second.id, Some("20".to_string())),
+                    (third.id, Some("30".to_string()))]);
+    assert_eq!(rows(&context_a, "alice", "shipped").await, vec![]);
+
+    // Queries which need more than the covered values still use the index to find candidates
+    let found: Vec<Entity> = context_a.query()
+                                      .eq("customer", "alice")
+                                      .eq("status", "open")
+                                      .compare("total", unbase::query::Comparison::Gt, "18")
+                                      .stream()
+                                      .try_collect()
+                                      .await
+                                      .unwrap();
+    let mut found: Vec<_> = found.into_iter().map(|entity| entity.id).collect();
+    found.sort();
+    assert_eq!(found, vec![second.id, third.id]);
+
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+    assert_eq!(rows(&context_b, "alice", "open").await.len(), 3);
+
+    simulator.quiesce_and_stop().await;
+}
+
+#[unbase_test_util::async_test]
+async fn composite_index_field_names() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    // Field names may contain any character, without one index being mistaken for another
+    context_a.declare_composite_index(&["a,b", "c"], &[]).await.unwrap();
+    assert!(IndexComposite::open(&context_a, &["a", "b", "c"]).await.unwrap().is_none());
+
+    let mut vals = HashMap::new();
+    vals.insert("a,b".to_string(), "1".to_string());
+    vals.insert("c".to_string(), "2".to_string());
+    let entity = Entity::new(&context_a, vals.clone()).await.unwrap();
+
+    let index = IndexComposite::open(&context_a, &["c", "a,b"]).await.unwrap().expect("declared");
+    let heads = index.get(&context_a, &vals).await.unwrap();
+    assert_eq!(heads.iter().map(|head| head.entity_id().unwrap()).collect::<Vec<_>>(), vec![entity.id]);
+}
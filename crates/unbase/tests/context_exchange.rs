@@ -0,0 +1,69 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+use unbase::{
+    context::CompactionPolicy,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn context_exchange() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    let slab_c = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+
+    let mut cow = Entity::new_with_single_kv(&context_a, "animal_sound", "Moo").await.unwrap();
+    simulator.quiesce().await;
+
+    // The memos have conveyed, but contexts created since have no path to them yet
+    let context_b = slab_b.create_context();
+    let context_c = slab_c.create_context();
+    assert!(context_b.get_entity(cow.id).await.unwrap().is_none());
+
+    // Which their slabs ask to be sent
+    simulator.quiesce().await;
+
+    let sent = context_a.send_context().await.unwrap();
+    assert!(sent > 0);
+    simulator.quiesce().await;
+
+    for context in &[&context_b, &context_c] {
+        let mut found = context.get_entity(cow.id).await.unwrap().expect("found after exchange");
+        assert_eq!(found.get_value("animal_sound").await.unwrap(), Some("Moo".to_string()));
+    }
+
+    // Exchanges may also follow each background compaction
+    context_a.set_compaction_policy(CompactionPolicy::new().max_writes(1)
+                                                           .exchange(true)
+                                                           .poll(Duration::from_millis(10)));
+
+    cow.set_value("animal_sound", "Moooo").await.unwrap();
+    let pig = Entity::new_with_single_kv(&context_a, "animal_sound", "Oink").await.unwrap();
+
+    let runs = context_a.compaction_metrics().background_runs;
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while context_a.compaction_metrics().background_runs == runs {
+        assert!(Instant::now() < deadline, "background compaction within the deadline");
+        async_std::task::sleep(Duration::from_millis(10)).await;
+    }
+    simulator.quiesce().await;
+
+    let mut found = context_b.get_entity(cow.id).await.unwrap().expect("found");
+    assert_eq!(found.get_value("animal_sound").await.unwrap(), Some("Moooo".to_string()));
+    assert!(context_c.get_entity(pig.id).await.unwrap().is_some());
+
+    simulator.quiesce_and_stop().await;
+}
@@ -0,0 +1,189 @@
+#[macro_use]
+extern crate serde_json;
+
+use unbase::{
+    error::{
+        DocumentError,
+        WriteError,
+    },
+    slab::MAX_SLOTS,
+    util::simulator::Simulator,
+    Network,
+    Slab,
+};
+
+use serde_json::Value;
+
+#[unbase_test_util::async_test]
+async fn document_roundtrip() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let document = json!({
+        "name": "Tiger",
+        "_id": "escaped",
+        "legs": 4,
+        "stripes": true,
+        "habitat": { "continent": "Asia", "range": [ "India", "Siberia" ] },
+        "diet": [ { "prey": "deer" }, null, 1.5 ]
+    });
+
+    let mut root = context_a.put_document(&document).await.expect("put_document");
+    assert_eq!(root.to_document().await.expect("to_document"), document);
+
+    assert_eq!(context_a.put_document(&json!("scalar")).await.unwrap_err(),
+               WriteError::DocumentError(DocumentError::NotAContainer));
+}
+
+#[unbase_test_util::async_test]
+async fn document_paths() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let mut root = context_a.put_document(&json!({ "habitat": { "range": [ "India" ] } }))
+                            .await
+                            .unwrap();
+
+    assert_eq!(root.get_path("habitat.range.0").await.unwrap(), Some(json!("India")));
+    assert_eq!(root.get_path("habitat.altitude").await.unwrap(), None);
+    assert_eq!(root.get_path("nowhere.at.all").await.unwrap(), None);
+
+    // Leaves are edited individually
+    root.set_path("habitat.range.0", &json!("Bengal")).await.unwrap();
+    root.set_path("habitat.range.1", &json!("Siberia")).await.unwrap();
+    root.set_path("habitat.climate", &json!({ "min": -40, "max": 45 })).await.unwrap();
+
+    assert_eq!(root.set_path("habitat.range.5", &json!("Mars")).await,
+               Err(WriteError::DocumentError(DocumentError::InvalidPath("habitat.range.5".to_string()))));
+
+    let expected: Value = json!({
+        "habitat": {
+            "range": [ "Bengal", "Siberia" ],
+            "climate": { "min": -40, "max": 45 }
+        }
+    });
+    assert_eq!(root.to_document().await.unwrap(), expected);
+
+    // An entity handle for the nested node observes edits made via the root
+    let mut found = context_a.try_fetch_kv("min", "-40").await.unwrap().expect("found");
+    root.set_path("habitat.climate.min", &json!(-50)).await.unwrap();
+    assert_eq!(found.to_document().await.unwrap(), json!({ "min": -50, "max": 45 }));
+}
+
+#[unbase_test_util::async_test]
+async fn document_concurrent_children() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let mut root_a = context_a.put_document(&json!({ "name": "Tiger" })).await.unwrap();
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    let mut root_b = context_b.get_entity(root_a.id).await.unwrap().expect("found");
+
+    // Each context adds a different child without knowing of the other's
+    root_a.set_path("habitat", &json!({ "continent": "Asia" })).await.unwrap();
+    root_b.set_path("diet", &json!({ "prey": "deer" })).await.unwrap();
+
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+    context_b.hack_send_context(&context_a).await.unwrap();
+
+    let expected = json!({
+        "name": "Tiger",
+        "habitat": { "continent": "Asia" },
+        "diet": { "prey": "deer" }
+    });
+
+    for context in &[&context_a, &context_b] {
+        let mut root = context.get_entity(root_a.id).await.unwrap().expect("found");
+        assert_eq!(root.to_document().await.unwrap(), expected);
+    }
+
+    simulator.quiesce_and_stop().await;
+}
+
+#[unbase_test_util::async_test]
+async fn document_child_limit() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let children: Vec<Value> = (0..MAX_SLOTS).map(|i| json!({ "n": i })).collect();
+
+    // A full node, whose keys can't all have the slots derived from them
+    let document = Value::Array(children.clone());
+    let mut root = context_a.put_document(&document).await.unwrap();
+    assert_eq!(root.to_document().await.unwrap(), document);
+
+    // Has no room for another child, and is left as it was
+    assert_eq!(root.set_path(&MAX_SLOTS.to_string(), &json!({ "n": "extra" })).await,
+               Err(WriteError::DocumentError(DocumentError::TooManyChildren)));
+    assert_eq!(root.to_document().await.unwrap(), document);
+
+    // Though a child may still be replaced, and scalars appended
+    root.set_path("0", &json!({ "n": "replaced" })).await.unwrap();
+    root.set_path(&MAX_SLOTS.to_string(), &json!("scalar")).await.unwrap();
+    assert_eq!(root.get_path("0.n").await.unwrap(), Some(json!("replaced")));
+    assert_eq!(root.get_path(&MAX_SLOTS.to_string()).await.unwrap(), Some(json!("scalar")));
+
+    let mut oversized = children;
+    oversized.push(json!({ "n": "extra" }));
+    assert_eq!(context_a.put_document(&Value::Array(oversized)).await.unwrap_err(),
+               WriteError::DocumentError(DocumentError::TooManyChildren));
+}
+
+#[unbase_test_util::async_test]
+async fn document_handoff() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let mut root_a = context_a.put_document(&json!({ "sightings": [] })).await.unwrap();
+
+    // Structural changes are single-writer, so each context appends only once it has seen the other's appends
+    for i in 0..4 {
+        let (context, other) = if i % 2 == 0 { (&context_a, &context_b) } else { (&context_b, &context_a) };
+
+        let mut root = context.get_entity(root_a.id).await.unwrap().expect("found");
+        root.set_path(&format!("sightings.{}", i), &json!({ "at": i })).await.unwrap();
+
+        simulator.quiesce().await;
+        context.hack_send_context(other).await.unwrap();
+    }
+
+    let expected = json!({ "sightings": [ { "at": 0 }, { "at": 1 }, { "at": 2 }, { "at": 3 } ] });
+    assert_eq!(root_a.to_document().await.unwrap(), expected);
+
+    let mut root_b = context_b.get_entity(root_a.id).await.unwrap().expect("found");
+    assert_eq!(root_b.to_document().await.unwrap(), expected);
+
+    simulator.quiesce_and_stop().await;
+}
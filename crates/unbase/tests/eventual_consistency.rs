@@ -165,9 +165,13 @@ async fn eventual_detail() {
     simulator.quiesce().await;
 
     // Nowwww it should have propagated
-    let expected_contents = "I9001>I9003;I9003>I9004;I9004>I9005;I9005>_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,\
-                             _,_,_,_,_,_,_,I9006;I9006>_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,\
-                             _,_,_,_,_,_,R9002";
+    // Index nodes beneath the root have ids derived from their position in the tree
+    let expected_contents = "I9001>I15082743300840488589;I5825257253658243141>_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,\
+                             _,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,R9002;I6052497632455603764>I15353298957690773421;\
+                             I10331510227894779248>I13182701423538456706;I13182701423538456706>_,_,_,_,_,_,_,_,_,_,_,_,\
+                             _,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,_,I5825257253658243141;\
+                             I14078218141233399751>I6052497632455603764;I15082743300840488589>I14078218141233399751;\
+                             I15353298957690773421>I10331510227894779248";
     assert_eq!(context_a.concise_contents(), expected_contents);
     assert_eq!(context_b.concise_contents(), expected_contents);
 
@@ -0,0 +1,58 @@
+use unbase::{
+    error::RetrieveError,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn fulltext_search() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let tigers = Entity::new_with_single_kv(&context_a, "description", "Tigers roar. A tiger is striped").await.unwrap();
+
+    assert_eq!(context_a.search("description", "tiger").await.unwrap_err(),
+               RetrieveError::IndexNotInitialized);
+
+    // Existing entities are indexed when the index is declared
+    context_a.declare_fulltext_index("description", true).await.unwrap();
+
+    let mut horse = Entity::new_with_single_kv(&context_a, "description", "A horse which is striped").await.unwrap();
+    let cat = Entity::new_with_single_kv(&context_a, "description", "Housecats purr, and a cat roared once").await
+                                                                                                         .unwrap();
+
+    let found = |hits: Vec<unbase::index::SearchHit>| hits.into_iter().map(|hit| hit.entity.id).collect::<Vec<_>>();
+
+    assert_eq!(found(context_a.search("description", "TIGER").await.unwrap()), vec![tigers.id]);
+    // Equally relevant hits are ordered by id
+    assert_eq!(found(context_a.search("description", "stripes").await.unwrap()), vec![tigers.id, horse.id]);
+
+    // The tigers mention their term twice, and roaring is rarer than being striped
+    let hits = context_a.search("description", "tiger roaring").await.unwrap();
+    assert_eq!(found(hits), vec![tigers.id, cat.id]);
+
+    // Edits replace the terms of the old value
+    horse.set_value("description", "A horse which gallops").await.unwrap();
+    assert_eq!(found(context_a.search("description", "striped").await.unwrap()), vec![tigers.id]);
+    assert_eq!(found(context_a.search("description", "galloping").await.unwrap()), vec![horse.id]);
+    assert!(context_a.search("description", "zebra").await.unwrap().is_empty());
+
+    // The index replicates like any other
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+    assert_eq!(found(context_b.search("description", "purring").await.unwrap()), vec![cat.id]);
+
+    simulator.quiesce_and_stop().await;
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+use unbase::{
+    context::Context,
+    slab::{
+        EntityId,
+        EntityType,
+    },
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+async fn insert_many(context: &Context, prefix: &str, count: usize) -> Vec<EntityId> {
+    let mut ids = Vec::new();
+    for i in 0..count {
+        let entity = Entity::new_with_single_kv(context, "name", &format!("{}{}", prefix, i)).await.unwrap();
+        ids.push(entity.id);
+    }
+    ids
+}
+
+#[unbase_test_util::async_test]
+async fn concurrent_index_inserts() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    let slab_c = Slab::new(&net);
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+    let context_c = slab_c.create_context();
+
+    // Both slabs insert into the same index nodes before hearing from one another
+    let mut expected = insert_many(&context_a, "a", 40).await;
+    expected.extend(insert_many(&context_b, "b", 40).await);
+
+    simulator.start();
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+    context_b.hack_send_context(&context_a).await.unwrap();
+
+    // A third slab which has only heard of the concurrent index heads, and none of the nodes beneath them
+    context_a.hack_send_context(&context_c).await.unwrap();
+    context_b.hack_send_context(&context_c).await.unwrap();
+
+    let expected: HashSet<EntityId> = expected.into_iter().collect();
+
+    // Neither slab's entries go missing, whether listed or looked up one by one
+    for context in &[&context_a, &context_b, &context_c] {
+        let listed: HashSet<EntityId> = context.entities_of_type(EntityType::Record)
+                                               .await
+                                               .unwrap()
+                                               .into_iter()
+                                               .map(|entity| entity.id)
+                                               .collect();
+        assert_eq!(listed, expected);
+
+        for entity_id in expected.iter() {
+            assert!(context.get_entity(*entity_id).await.unwrap().is_some(), "{} not found", entity_id);
+        }
+    }
+
+    simulator.quiesce_and_stop().await;
+}
@@ -0,0 +1,85 @@
+use unbase::{
+    error::{
+        IndexShapeError,
+        WriteError,
+    },
+    index::IndexShape,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn index_shape_recorded_in_seed() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system_with_index_shape(IndexShape::new(16, 16)).unwrap();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    assert_eq!(context_a.root_index().await.unwrap().shape(), IndexShape::new(16, 16));
+
+    let tiger = Entity::new_with_single_kv(&context_a, "animal", "Tiger").await.unwrap();
+
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    // Slab B learns the shape from the seed
+    assert_eq!(context_b.root_index().await.unwrap().shape(), IndexShape::new(16, 16));
+    let mut found = context_b.get_entity(tiger.id).await.unwrap().expect("found");
+    assert_eq!(found.get_value("animal").await.unwrap(), Some("Tiger".to_string()));
+
+    simulator.quiesce_and_stop().await;
+}
+
+#[test]
+fn index_shape_too_narrow() {
+    assert!(Network::create_new_system_with_index_shape(IndexShape::new(16, 15)).is_err());
+    assert!(Network::create_new_system_with_index_shape(IndexShape::new(2, 64)).is_ok());
+}
+
+#[unbase_test_util::async_test]
+async fn index_reindex() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let mut records = Vec::new();
+    for i in 0..50 {
+        records.push(Entity::new_with_single_kv(&context_a, "record number", &i.to_string()).await.unwrap());
+    }
+
+    // Shapes which would alias entity ids are refused
+    assert_eq!(context_a.reindex(IndexShape::new(4, 6)).await,
+               Err(WriteError::IndexShapeError(IndexShapeError::TooNarrow { shape_bits: 12,
+                                                                            key_bits:   64, })));
+
+    context_a.reindex(IndexShape::new(4, 32)).await.unwrap();
+    assert_eq!(context_a.root_index().await.unwrap().shape(), IndexShape::new(4, 32));
+
+    // Writes after the migration land in the new shape
+    let late = Entity::new_with_single_kv(&context_a, "record number", "late").await.unwrap();
+    records.push(late);
+
+    // A fresh context follows the old root to its successor
+    let context_b = slab_a.create_context();
+    context_a.hack_send_context(&context_b).await.unwrap();
+    assert_eq!(context_b.root_index().await.unwrap().shape(), IndexShape::new(4, 32));
+
+    for context in &[&context_a, &context_b] {
+        for record in records.iter() {
+            let found = context.get_entity(record.id).await.unwrap().expect("found");
+            assert_eq!(found.id, record.id);
+        }
+    }
+}
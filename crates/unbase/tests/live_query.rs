@@ -0,0 +1,89 @@
+use async_std::future::timeout;
+use futures::StreamExt;
+use std::time::Duration;
+use unbase::{
+    query::{
+        LiveQuery,
+        QueryEvent,
+    },
+    Entity,
+    Network,
+    Slab,
+};
+
+async fn next_event(live: &mut LiveQuery) -> QueryEvent {
+    timeout(Duration::from_secs(5), live.next()).await
+                                                .expect("event within the deadline")
+                                                .expect("stream open")
+                                                .expect("no error")
+}
+
+#[unbase_test_util::async_test]
+async fn live_query_events() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let mut tiger = Entity::new_with_single_kv(&context_a, "animal", "Tiger").await.unwrap();
+    Entity::new_with_single_kv(&context_a, "animal", "Cow").await.unwrap();
+
+    let query = context_a.query().eq("animal", "Tiger").select(&["animal", "sound"]);
+    let mut live = context_a.live_query(query);
+
+    // The initial results
+    match next_event(&mut live).await {
+        QueryEvent::Added(row) => assert_eq!(row.entity_id, tiger.id),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // A new match
+    let other = Entity::new_with_single_kv(&context_a, "animal", "Tiger").await.unwrap();
+    match next_event(&mut live).await {
+        QueryEvent::Added(row) => assert_eq!(row.entity_id, other.id),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // A change to a selected field
+    tiger.set_value("sound", "Rawwr").await.unwrap();
+    match next_event(&mut live).await {
+        QueryEvent::Changed(row) => {
+            assert_eq!(row.entity_id, tiger.id);
+            assert_eq!(row.values.get("sound"), Some(&"Rawwr".to_string()));
+        },
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // No longer a match
+    tiger.set_value("animal", "Housecat").await.unwrap();
+    match next_event(&mut live).await {
+        QueryEvent::Removed(entity_id) => assert_eq!(entity_id, tiger.id),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[unbase_test_util::async_test]
+async fn live_query_snapshot() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let mut tiger = Entity::new_with_single_kv(&context_a, "animal", "Tiger").await.unwrap();
+
+    let snapshot = context_a.snapshot();
+    let mut live = snapshot.live_query(snapshot.query().eq("animal", "Tiger"));
+
+    match next_event(&mut live).await {
+        QueryEvent::Added(row) => assert_eq!(row.entity_id, tiger.id),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // Writes to the context are not seen by the snapshot, and cause no errors either
+    tiger.set_value("animal", "Housecat").await.unwrap();
+    Entity::new_with_single_kv(&context_a, "animal", "Tiger").await.unwrap();
+
+    assert!(timeout(Duration::from_millis(200), live.next()).await.is_err());
+}
@@ -0,0 +1,65 @@
+use unbase::{
+    slab::EntityId,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn natural_key_local() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let mut alice = Entity::get_or_create_by_key(&context_a, "users", "alice@example.com").await.unwrap();
+    assert_eq!(alice.id, EntityId::from_natural_key("users", "alice@example.com"));
+    alice.set_value("name", "Alice").await.unwrap();
+
+    let mut again = Entity::get_or_create_by_key(&context_a, "users", "alice@example.com").await.unwrap();
+    assert_eq!(again.id, alice.id);
+    assert_eq!(again.get_value("name").await.unwrap(), Some("Alice".to_string()));
+
+    // The same key in another namespace is another entity
+    let other = Entity::get_or_create_by_key(&context_a, "admins", "alice@example.com").await.unwrap();
+    assert_ne!(other.id, alice.id);
+}
+
+#[unbase_test_util::async_test]
+async fn natural_key_concurrent_creation() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    // Both slabs create the entity before hearing from one another
+    let mut user_a = Entity::get_or_create_by_key(&context_a, "users", "bob@example.com").await.unwrap();
+    let mut user_b = Entity::get_or_create_by_key(&context_b, "users", "bob@example.com").await.unwrap();
+    assert_eq!(user_a.id, user_b.id);
+
+    user_a.set_value("name", "Bob").await.unwrap();
+    user_b.set_value("phone", "555-1234").await.unwrap();
+
+    simulator.start();
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+    context_b.hack_send_context(&context_a).await.unwrap();
+
+    // Each slab sees one entity with both edits
+    for context in &[&context_a, &context_b] {
+        let mut user = Entity::get_or_create_by_key(context, "users", "bob@example.com").await.unwrap();
+        assert_eq!(user.get_value("name").await.unwrap(), Some("Bob".to_string()));
+        assert_eq!(user.get_value("phone").await.unwrap(), Some("555-1234".to_string()));
+    }
+
+    simulator.quiesce_and_stop().await;
+}
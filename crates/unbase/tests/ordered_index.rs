@@ -0,0 +1,123 @@
+use futures::TryStreamExt;
+use std::collections::HashMap;
+use unbase::{
+    head::Head,
+    index::{
+        IndexOrdered,
+        OrderedKey,
+    },
+    query::Comparison,
+    slab::{
+        EdgeSet,
+        EntityId,
+        EntityType,
+        MemoBody,
+        RelationSet,
+    },
+    Entity,
+    Network,
+    Slab,
+};
+
+async fn query_ids(context: &unbase::context::Context, comparison: Comparison, value: &str) -> Vec<EntityId> {
+    let found: Vec<Entity> = context.query()
+                                    .compare("created_at", comparison, value)
+                                    .stream()
+                                    .try_collect()
+                                    .await
+                                    .unwrap();
+
+    let mut ids: Vec<EntityId> = found.iter().map(|entity| entity.id).collect();
+    ids.sort();
+    ids
+}
+
+fn sorted(mut ids: Vec<EntityId>) -> Vec<EntityId> {
+    ids.sort();
+    ids
+}
+
+#[unbase_test_util::async_test]
+async fn ordered_index_maintenance() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    // Existing entities are indexed when the index is declared
+    let mut orders = Vec::new();
+    for created_at in ["30", "5", "120"].iter() {
+        orders.push(Entity::new_with_single_kv(&context_a, "created_at", created_at).await.unwrap());
+    }
+
+    context_a.declare_ordered_index("created_at").await.unwrap();
+
+    // As are new ones
+    for created_at in ["7.5", "1000", "-2"].iter() {
+        orders.push(Entity::new_with_single_kv(&context_a, "created_at", created_at).await.unwrap());
+    }
+
+    let index = IndexOrdered::open(&context_a, "created_at").await.unwrap().expect("declared");
+
+    // Entries are read in numeric order, either way around
+    let keys: Vec<OrderedKey> = index.range(&context_a, ..).await.unwrap().into_iter().map(|(key, _)| key).collect();
+    let expected: Vec<OrderedKey> = [-2.0, 5.0, 7.5, 30.0, 120.0, 1000.0].iter().map(|f| OrderedKey::Float(*f)).collect();
+    assert_eq!(keys, expected);
+
+    let rev = index.range_rev(&context_a, OrderedKey::Float(5.0)..OrderedKey::Float(120.0)).await.unwrap();
+    let ids: Vec<EntityId> = rev.iter().map(|(_, head)| head.entity_id().unwrap()).collect();
+    assert_eq!(ids, vec![orders[0].id, orders[3].id, orders[1].id]);
+
+    // And a change to the value moves the entity
+    orders[2].set_value("created_at", "6").await.unwrap();
+    let found = index.range(&context_a, OrderedKey::Float(5.0)..=OrderedKey::Float(7.5)).await.unwrap();
+    let ids: Vec<EntityId> = found.iter().map(|(_, head)| head.entity_id().unwrap()).collect();
+    assert_eq!(ids, vec![orders[1].id, orders[2].id, orders[3].id]);
+    assert!(index.range(&context_a, OrderedKey::Float(120.0)..=OrderedKey::Float(120.0)).await.unwrap().is_empty());
+}
+
+#[unbase_test_util::async_test]
+async fn ordered_index_query() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    context_a.declare_ordered_index("created_at").await.unwrap();
+
+    let mut ids = Vec::new();
+    for created_at in ["10", "20", "30", "40", "soon"].iter() {
+        ids.push(Entity::new_with_single_kv(&context_a, "created_at", created_at).await.unwrap().id);
+    }
+
+    // An entity which the index does not list, as written by a context unaware of it
+    let unlisted = EntityId { id:    4242,
+                              stype: EntityType::Record, };
+    let mut values = HashMap::new();
+    values.insert("created_at".to_string(), "25".to_string());
+    let head = context_a.slab
+                        .new_memo(Some(unlisted),
+                                  Head::Null,
+                                  MemoBody::FullyMaterialized { v: values,
+                                                                r: RelationSet::empty(),
+                                                                e: EdgeSet::empty(),
+                                                                t: EntityType::Record, })
+                        .to_head();
+    context_a.root_index().await.unwrap().insert(&context_a, unlisted.id, head).await.unwrap();
+
+    // Comparisons draw their candidates from the index, so the unlisted entity is not found
+    assert_eq!(query_ids(&context_a, Comparison::Gt, "20").await, sorted(vec![ids[2], ids[3], ids[4]]));
+    assert_eq!(query_ids(&context_a, Comparison::Ge, "20").await, sorted(vec![ids[1], ids[2], ids[3], ids[4]]));
+    assert_eq!(query_ids(&context_a, Comparison::Lt, "30").await, sorted(vec![ids[0], ids[1]]));
+    assert_eq!(query_ids(&context_a, Comparison::Le, "10").await, vec![ids[0]]);
+
+    // Values which are not numbers are compared lexically, just as they are without the index
+    assert_eq!(query_ids(&context_a, Comparison::Gt, "later").await, vec![ids[4]]);
+    assert_eq!(query_ids(&context_a, Comparison::Lt, "later").await, sorted(vec![ids[0], ids[1], ids[2], ids[3]]));
+
+    // Other predicates are not answered from the index
+    let found: Vec<Entity> = context_a.query().eq("created_at", "25").stream().try_collect().await.unwrap();
+    assert_eq!(found.iter().map(|entity| entity.id).collect::<Vec<_>>(), vec![unlisted]);
+}
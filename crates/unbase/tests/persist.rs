@@ -0,0 +1,53 @@
+use unbase::{
+    context::Context,
+    error::RetrieveError,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn save_and_restore() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let mut cow = Entity::new_with_single_kv(&context_a, "animal_sound", "Moo").await.unwrap();
+    cow.set_value("animal_sound", "Moooo").await.unwrap();
+    let cow_id = cow.id;
+    simulator.quiesce().await;
+
+    let saved = context_a.save();
+    drop(cow);
+    drop(context_a);
+
+    // The same slab picks up where it left off
+    let restored = Context::restore(&slab_a, &saved).await.unwrap();
+    let mut found = restored.get_entity(cow_id).await.unwrap().expect("found after restore");
+    assert_eq!(found.get_value("animal_sound").await.unwrap(), Some("Moooo".to_string()));
+
+    // As does another slab, standing in for the process after a restart. A context created there without the saved
+    // one has no path to the write
+    let slab_c = Slab::new(&net);
+    simulator.quiesce().await;
+
+    let fresh = slab_c.create_context();
+    assert!(fresh.get_entity(cow_id).await.unwrap().is_none());
+
+    let restored = Context::restore(&slab_c, &saved).await.unwrap();
+    let mut found = restored.get_entity(cow_id).await.unwrap().expect("found after restore");
+    assert_eq!(found.get_value("animal_sound").await.unwrap(), Some("Moooo".to_string()));
+
+    assert_eq!(Context::restore(&slab_b, b"garbage").await.err(), Some(RetrieveError::Malformed));
+    assert_eq!(Context::restore(&slab_b, b"[99]").await.err(), Some(RetrieveError::Malformed));
+
+    simulator.quiesce_and_stop().await;
+}
@@ -0,0 +1,206 @@
+use futures::{
+    StreamExt,
+    TryStreamExt,
+};
+use std::collections::HashMap;
+use unbase::{
+    error::QueryError,
+    query::{
+        Aggregate,
+        AggregateValue,
+        Comparison,
+        Group,
+        Query,
+        Row,
+    },
+    schema::{
+        Schema,
+        ValueType,
+    },
+    Entity,
+    Network,
+    Slab,
+};
+
+async fn animal(context: &unbase::context::Context, name: &str, legs: &str) -> Entity {
+    let mut vals = HashMap::new();
+    vals.insert("animal".to_string(), name.to_string());
+    vals.insert("legs".to_string(), legs.to_string());
+
+    Entity::new(context, vals).await.unwrap()
+}
+
+async fn names(mut entities: Vec<Entity>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for entity in entities.iter_mut() {
+        names.push(entity.get_value("animal").await.unwrap().unwrap());
+    }
+    names.sort();
+    names
+}
+
+#[unbase_test_util::async_test]
+async fn query_predicates() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    animal(&context_a, "Tiger", "4").await;
+    animal(&context_a, "Ostrich", "2").await;
+    animal(&context_a, "Tick", "8").await;
+    let mut snake = animal(&context_a, "Snake", "0").await;
+    snake.set_value("venomous", "yes").await.unwrap();
+
+    let found = context_a.query().eq("animal", "Tiger").stream().try_collect().await.unwrap();
+    assert_eq!(names(found).await, vec!["Tiger"]);
+
+    // Numeric values compare numerically
+    let found = context_a.query().compare("legs", Comparison::Gt, "2").stream().try_collect().await.unwrap();
+    assert_eq!(names(found).await, vec!["Tick", "Tiger"]);
+
+    let found = context_a.query()
+                         .prefix("animal", "Ti")
+                         .compare("legs", Comparison::Le, "4")
+                         .stream()
+                         .try_collect()
+                         .await
+                         .unwrap();
+    assert_eq!(names(found).await, vec!["Tiger"]);
+
+    let found = context_a.query().exists("venomous").stream().try_collect().await.unwrap();
+    assert_eq!(names(found).await, vec!["Snake"]);
+
+    // Limit and offset
+    let all: Vec<Entity> = context_a.query().exists("animal").stream().try_collect().await.unwrap();
+    assert_eq!(all.len(), 4);
+    assert_eq!(context_a.query().exists("animal").limit(3).stream().count().await, 3);
+    assert_eq!(context_a.query().exists("animal").offset(3).limit(3).stream().count().await, 1);
+
+    // The same results are found by way of a secondary index
+    context_a.declare_index("animal").await.unwrap();
+    let found = context_a.query().eq("animal", "Tiger").stream().try_collect().await.unwrap();
+    assert_eq!(names(found).await, vec!["Tiger"]);
+}
+
+#[unbase_test_util::async_test]
+async fn query_traversal_and_projection() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let tiger = animal(&context_a, "Tiger", "4").await;
+    let mut cub = animal(&context_a, "Tiger cub", "4").await;
+    cub.set_relation(0, &tiger).await.unwrap();
+    let mut chick = animal(&context_a, "Chick", "2").await;
+    let ostrich = animal(&context_a, "Ostrich", "2").await;
+    chick.set_relation(0, &ostrich).await.unwrap();
+
+    // Parents of the four legged
+    let found = context_a.query().eq("legs", "4").traverse(0).stream().try_collect().await.unwrap();
+    assert_eq!(names(found).await, vec!["Tiger"]);
+
+    let rows: Vec<_> = context_a.query()
+                                .eq("animal", "Chick")
+                                .traverse(0)
+                                .select(&["animal"])
+                                .rows()
+                                .try_collect()
+                                .await
+                                .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].entity_id, ostrich.id);
+    assert_eq!(rows[0].values.len(), 1);
+    assert_eq!(rows[0].values.get("animal"), Some(&"Ostrich".to_string()));
+}
+
+#[unbase_test_util::async_test]
+async fn query_text() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    animal(&context_a, "Tiger", "4").await;
+    animal(&context_a, "Ostrich", "2").await;
+    animal(&context_a, "Tick", "8").await;
+
+    let query = Query::parse(&context_a, "SELECT animal FROM Record WHERE animal LIKE 'Ti%' AND legs < 5").await.unwrap();
+    let rows: Vec<Row> = query.rows().try_collect().await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].values.get("animal"), Some(&"Tiger".to_string()));
+    assert_eq!(rows[0].values.len(), 1);
+
+    match Query::parse(&context_a, "SELECT * WHERE legs >").await {
+        Err(QueryError::Parse { position: 21, .. }) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // Types other than Record must have a schema, which constrains the fields
+    match Query::parse(&context_a, "SELECT * FROM Beast").await {
+        Err(QueryError::Plan { position: 14, .. }) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+
+    context_a.register_schema(&Schema::new("Beast").field("sound", ValueType::String)).await.unwrap();
+    match Query::parse(&context_a, "SELECT * FROM Beast WHERE legs = 4").await {
+        Err(QueryError::Plan { position: 26, .. }) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let mut vals = HashMap::new();
+    vals.insert("sound".to_string(), "Moo".to_string());
+    Entity::new_typed(&context_a, Schema::type_id_for("Beast"), vals).await.unwrap();
+
+    let query = Query::parse(&context_a, "select sound from Beast where sound = 'Moo'").await.unwrap();
+    assert_eq!(query.stream().count().await, 1);
+}
+
+#[unbase_test_util::async_test]
+async fn query_aggregates() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    animal(&context_a, "Tiger", "4").await;
+    animal(&context_a, "Tiger", "3").await;
+    animal(&context_a, "Ostrich", "2").await;
+    animal(&context_a, "Tick", "8").await;
+
+    let groups = context_a.query()
+                          .aggregate(Aggregate::Count)
+                          .aggregate(Aggregate::Sum("legs".to_string()))
+                          .aggregate(Aggregate::Min("legs".to_string()))
+                          .aggregate(Aggregate::Max("animal".to_string()))
+                          .aggregate(Aggregate::Avg("missing".to_string()))
+                          .groups()
+                          .await
+                          .unwrap();
+    assert_eq!(groups,
+               vec![Group { key:    None,
+                            values: vec![AggregateValue::Count(4),
+                                         AggregateValue::Number(17.0),
+                                         AggregateValue::Value("2".to_string()),
+                                         AggregateValue::Value("Tiger".to_string()),
+                                         AggregateValue::Null], }]);
+
+    let query = Query::parse(&context_a, "SELECT animal, count(*), avg(legs) WHERE legs > 2 GROUP BY animal").await
+                                                                                                             .unwrap();
+    let groups = query.groups().await.unwrap();
+    assert_eq!(groups,
+               vec![Group { key:    Some("Tick".to_string()),
+                            values: vec![AggregateValue::Count(1), AggregateValue::Number(8.0)], },
+                    Group { key:    Some("Tiger".to_string()),
+                            values: vec![AggregateValue::Count(2), AggregateValue::Number(3.5)], },]);
+
+    match Query::parse(&context_a, "SELECT legs, count(*) GROUP BY animal").await {
+        Err(QueryError::Plan { position: 7, .. }) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+}
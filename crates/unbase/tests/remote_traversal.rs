@@ -48,8 +48,10 @@ async fn remote_traversal_simulated() {
 
     simulator.quiesce_and_stop().await;
 
-    assert_eq!(simulator.get_sent().unwrap(), 37);
-    assert_eq!(simulator.get_delivered().unwrap(), 37);
+    // The root index has one tier per byte of the u64 entity id, each of which is an index node to be sent. Each slab
+    // also asks the other for its context exchanges
+    assert_eq!(simulator.get_sent().unwrap(), 51);
+    assert_eq!(simulator.get_delivered().unwrap(), 51);
     assert_eq!(simulator.get_clock().unwrap(), 7);
 }
 
@@ -0,0 +1,56 @@
+use unbase::{
+    context::ScanToken,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn scan_pages() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let mut created = Vec::new();
+    for i in 0..25 {
+        let entity = Entity::new_with_single_kv(&context_a, "number", &i.to_string()).await.unwrap();
+        created.push(entity.id);
+    }
+    created.sort();
+
+    let mut scan = context_a.scan().page_size(7);
+    let mut scanned = Vec::new();
+    let mut page_sizes = Vec::new();
+    let mut token = None;
+
+    loop {
+        let page = scan.next_page().await.unwrap();
+        if page.is_empty() {
+            break;
+        }
+
+        page_sizes.push(page.len());
+        scanned.extend(page.into_iter().map(|entity| entity.id));
+
+        if page_sizes.len() == 2 {
+            token = Some(scan.token().to_string());
+        }
+    }
+
+    assert!(scan.is_finished());
+    assert_eq!(page_sizes, vec![7, 7, 7, 4]);
+
+    // Entities are visited in key order, and exactly once
+    assert_eq!(scanned, created);
+
+    // Resume a fresh scan from the token which was issued after the second page
+    let token: ScanToken = token.unwrap().parse().unwrap();
+    let mut resumed = context_a.scan().page_size(100).resume(token);
+    let rest: Vec<_> = resumed.next_page().await.unwrap().into_iter().map(|entity| entity.id).collect();
+
+    assert_eq!(rest, created[14..].to_vec());
+    assert!(resumed.is_finished());
+    assert!("garbage".parse::<ScanToken>().is_err());
+}
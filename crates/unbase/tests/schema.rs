@@ -0,0 +1,157 @@
+use unbase::{
+    error::{
+        SchemaViolation,
+        WriteError,
+    },
+    schema::{
+        Migration,
+        RelationTarget,
+        Schema,
+        ValueType,
+    },
+    slab::EntityType,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+use std::collections::HashMap;
+
+#[unbase_test_util::async_test]
+async fn schema_validation() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let animal = Schema::new("Animal").field("name", ValueType::String)
+                                      .field("legs", ValueType::Integer)
+                                      .relation(0, RelationTarget::Record);
+
+    // Writes to a type with no registered schema are rejected
+    assert_eq!(Entity::new_typed(&context_a, animal.type_id, HashMap::new()).await.unwrap_err(),
+               WriteError::SchemaViolation(SchemaViolation::SchemaNotFound(animal.type_id)));
+
+    context_a.register_schema(&animal).await.expect("register schema");
+    assert_eq!(context_a.get_schema(animal.type_id).await.unwrap(), Some(animal.clone()));
+
+    let mut vals = HashMap::new();
+    vals.insert("name".to_string(), "Cow".to_string());
+    vals.insert("legs".to_string(), "4".to_string());
+
+    let mut cow = Entity::new_typed(&context_a, animal.type_id, vals).await.expect("valid entity");
+    assert_eq!(cow.id.stype, EntityType::Custom(animal.type_id));
+
+    cow.set_value("legs", "3").await.expect("valid write");
+    assert_eq!(cow.set_value("legs", "four").await,
+               Err(WriteError::SchemaViolation(SchemaViolation::InvalidValue { field:    "legs".to_string(),
+                                                                               expected: ValueType::Integer, })));
+    assert_eq!(cow.set_value("sound", "Moo").await,
+               Err(WriteError::SchemaViolation(SchemaViolation::UnknownField("sound".to_string()))));
+    assert_eq!(cow.get_value("legs").await.unwrap(), Some("3".to_string()));
+
+    let farmer = Entity::new_with_single_kv(&context_a, "name", "Old McDonald").await.unwrap();
+    cow.set_relation(0, &farmer).await.expect("valid relation");
+    assert_eq!(cow.set_relation(1, &farmer).await,
+               Err(WriteError::SchemaViolation(SchemaViolation::UnknownRelation(1))));
+
+    let calf = Entity::new_typed(&context_a, animal.type_id, HashMap::new()).await.unwrap();
+    assert_eq!(cow.set_relation(0, &calf).await,
+               Err(WriteError::SchemaViolation(SchemaViolation::InvalidRelationTarget { slot_id: 0,
+                                                                                        target:  calf.id.stype, })));
+}
+
+#[unbase_test_util::async_test]
+async fn schema_not_a_record() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let animal = Schema::new("Animal").field("name", ValueType::String);
+    context_a.register_schema(&animal).await.unwrap();
+
+    // The schema is listed in the root index with its name, but a scan for records by value must pass it over
+    assert!(context_a.try_fetch_kv("name", "Animal").await.unwrap().is_none());
+
+    let record = Entity::new_with_single_kv(&context_a, "name", "Animal").await.unwrap();
+    let found = context_a.try_fetch_kv("name", "Animal").await.unwrap().expect("found");
+    assert_eq!(found.id, record.id);
+}
+
+#[unbase_test_util::async_test]
+async fn schema_replication() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let animal = Schema::new("Animal").field("name", ValueType::String);
+    context_a.register_schema(&animal).await.unwrap();
+
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    // The schema is stored in the system itself, so slab B can enforce it too
+    assert_eq!(context_b.get_schema(animal.type_id).await.unwrap(), Some(animal.clone()));
+
+    let mut tiger = Entity::new_typed(&context_b, animal.type_id, HashMap::new()).await.unwrap();
+    tiger.set_value("name", "Tiger").await.unwrap();
+    assert!(tiger.set_value("stripes", "many").await.is_err());
+
+    simulator.quiesce_and_stop().await;
+}
+
+#[unbase_test_util::async_test]
+async fn schema_migration() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let v1 = Schema::new("Animal").field("legs", ValueType::Integer)
+                                  .field("sound", ValueType::String);
+    context_a.register_schema(&v1).await.unwrap();
+
+    let mut cow = Entity::new_typed(&context_a, v1.type_id, HashMap::new()).await.unwrap();
+    cow.set_value("legs", "4").await.unwrap();
+    cow.set_value("sound", "moo").await.unwrap();
+
+    // Version 2 renames legs to leg_count, and shouts
+    let v2 = Schema::new("Animal").version(2)
+                                  .field("leg_count", ValueType::Integer)
+                                  .field("sound", ValueType::String);
+    context_a.register_schema(&v2).await.unwrap();
+    context_a.register_migration(v2.type_id,
+                                 Migration::new(1, 2).rename("legs", "leg_count")
+                                                     .transform("sound", |v| v.to_uppercase()));
+
+    // Old memos are projected through the migration on read
+    assert_eq!(cow.get_value("leg_count").await.unwrap(), Some("4".to_string()));
+    assert_eq!(cow.get_value("legs").await.unwrap(), None);
+    assert_eq!(cow.get_value("sound").await.unwrap(), Some("MOO".to_string()));
+
+    // Writes are validated against the new version
+    assert!(cow.set_value("legs", "3").await.is_err());
+    cow.set_value("leg_count", "3").await.unwrap();
+    assert_eq!(cow.get_value("leg_count").await.unwrap(), Some("3".to_string()));
+
+    // The keyframe job rewrites the entity in the new shape exactly once
+    assert_eq!(context_a.write_keyframes(v2.type_id).await.unwrap(), 1);
+    assert_eq!(context_a.write_keyframes(v2.type_id).await.unwrap(), 0);
+
+    assert_eq!(cow.get_value("leg_count").await.unwrap(), Some("3".to_string()));
+    assert_eq!(cow.get_value("sound").await.unwrap(), Some("MOO".to_string()));
+}
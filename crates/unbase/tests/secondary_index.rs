@@ -0,0 +1,140 @@
+use futures::TryStreamExt;
+use unbase::{
+    index::IndexSecondary,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+use std::time::Duration;
+
+#[unbase_test_util::async_test]
+async fn secondary_index_maintenance() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    let tiger = Entity::new_with_single_kv(&context_a, "animal", "Tiger").await.unwrap();
+    let _cow = Entity::new_with_single_kv(&context_a, "animal", "Cow").await.unwrap();
+
+    assert!(IndexSecondary::open(&context_a, "animal").await.unwrap().is_none());
+
+    // Existing entities are indexed when the index is declared
+    context_a.declare_index("animal").await.unwrap();
+    let index = IndexSecondary::open(&context_a, "animal").await.unwrap().expect("declared");
+    assert_eq!(index.get(&context_a, "Tiger").await.unwrap().len(), 1);
+
+    // As are new ones
+    let mut cat = Entity::new_with_single_kv(&context_a, "animal", "Tiger").await.unwrap();
+    assert_eq!(index.get(&context_a, "Tiger").await.unwrap().len(), 2);
+
+    // And changes to the value move the entity to the posting for the new value
+    cat.set_value("animal", "Housecat").await.unwrap();
+    assert_eq!(index.get(&context_a, "Tiger").await.unwrap().len(), 1);
+    assert_eq!(index.get(&context_a, "Housecat").await.unwrap().len(), 1);
+
+    let found = context_a.try_fetch_kv("animal", "Tiger").await.unwrap().expect("found");
+    assert_eq!(found.id, tiger.id);
+    let found = context_a.try_fetch_kv("animal", "Housecat").await.unwrap().expect("found");
+    assert_eq!(found.id, cat.id);
+    assert!(context_a.try_fetch_kv("animal", "Walrus").await.unwrap().is_none());
+}
+
+#[unbase_test_util::async_test]
+async fn secondary_index_remote() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    // B has written before, and so has already looked for indexes and found none
+    Entity::new_with_single_kv(&context_b, "name", "Bob").await.unwrap();
+
+    context_a.declare_index("email").await.unwrap();
+    let alice = Entity::new_with_single_kv(&context_a, "email", "alice@example.com").await.unwrap();
+
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    // Slab B discovers the index via the root index, and uses it
+    let index = IndexSecondary::open(&context_b, "email").await.unwrap().expect("declared");
+    let found = context_b.fetch_kv("email", "alice@example.com", Duration::from_secs(1)).await.unwrap();
+    assert_eq!(found.id, alice.id);
+
+    // ...and maintains it
+    let carol = Entity::new_with_single_kv(&context_b, "email", "carol@example.com").await.unwrap();
+    let heads = index.get(&context_b, "carol@example.com").await.unwrap();
+    assert_eq!(heads.iter().map(|head| head.entity_id().unwrap()).collect::<Vec<_>>(), vec![carol.id]);
+
+    simulator.quiesce_and_stop().await;
+}
+
+#[unbase_test_util::async_test]
+async fn secondary_index_unaware_writer() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    // B writes without having heard of the index, so the index does not list its entity
+    context_a.declare_index("email").await.unwrap();
+    let bob = Entity::new_with_single_kv(&context_b, "email", "bob@example.com").await.unwrap();
+
+    simulator.quiesce().await;
+    context_b.hack_send_context(&context_a).await.unwrap();
+
+    let index = IndexSecondary::open(&context_a, "email").await.unwrap().expect("declared");
+    assert!(index.get(&context_a, "bob@example.com").await.unwrap().is_empty());
+
+    // The index is all that lookups consult, whether by fetch or by query
+    assert!(context_a.try_fetch_kv("email", "bob@example.com").await.unwrap().is_none());
+    let queried: Vec<Entity> = context_a.query().eq("email", "bob@example.com").stream().try_collect().await.unwrap();
+    assert!(queried.is_empty());
+
+    // Until the index is declared again, which lists whatever it is missing
+    context_a.declare_index("email").await.unwrap();
+    assert_eq!(index.get(&context_a, "bob@example.com").await.unwrap().len(), 1);
+
+    let found = context_a.try_fetch_kv("email", "bob@example.com").await.unwrap().expect("found");
+    assert_eq!(found.id, bob.id);
+    let queried: Vec<Entity> = context_a.query().eq("email", "bob@example.com").stream().try_collect().await.unwrap();
+    assert_eq!(queried.iter().map(|entity| entity.id).collect::<Vec<_>>(), vec![bob.id]);
+
+    simulator.quiesce_and_stop().await;
+}
+
+#[unbase_test_util::async_test]
+async fn secondary_index_own_key() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    context_a.declare_index("beast").await.unwrap();
+    let record = Entity::new_with_single_kv(&context_a, "field", "beast").await.unwrap();
+
+    // The root of the index records its field under the same key, but is not a record
+    let found = context_a.try_fetch_kv("field", "beast").await.unwrap().expect("found");
+    assert_eq!(found.id, record.id);
+    assert!(context_a.try_fetch_kv("field", "animal").await.unwrap().is_none());
+}
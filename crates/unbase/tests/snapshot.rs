@@ -0,0 +1,59 @@
+use unbase::{
+    error::WriteError,
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+#[unbase_test_util::async_test]
+async fn snapshot_repeatable_reads() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let mut cow = Entity::new_with_single_kv(&context_a, "animal_sound", "Moo").await.unwrap();
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    let snapshot = context_b.snapshot();
+    assert!(snapshot.is_read_only());
+    assert!(!context_b.is_read_only());
+
+    // Writes arriving after the snapshot was taken are applied to the context, but not to the snapshot
+    cow.set_value("animal_sound", "Moooo").await.unwrap();
+    let pig = Entity::new_with_single_kv(&context_a, "animal_sound", "Oink").await.unwrap();
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    let mut live = context_b.get_entity(cow.id).await.unwrap().expect("found");
+    assert_eq!(live.get_value("animal_sound").await.unwrap(), Some("Moooo".to_string()));
+    assert!(context_b.get_entity(pig.id).await.unwrap().is_some());
+
+    let mut frozen = snapshot.get_entity(cow.id).await.unwrap().expect("found");
+    assert_eq!(frozen.get_value("animal_sound").await.unwrap(), Some("Moo".to_string()));
+    assert!(snapshot.get_entity(pig.id).await.unwrap().is_none());
+
+    // Writes are rejected before any memo is issued
+    let memos = slab_b.count_of_memorefs_resident();
+    assert_eq!(frozen.set_value("animal_sound", "Baa").await, Err(WriteError::ReadOnly));
+    assert_eq!(Entity::new_with_single_kv(&snapshot, "animal_sound", "Baa").await.err(),
+               Some(WriteError::ReadOnly));
+    assert_eq!(snapshot.compact().await.err(), Some(WriteError::ReadOnly));
+    assert_eq!(slab_b.count_of_memorefs_resident(), memos);
+
+    // Repeated reads still see the same cut
+    let mut frozen = snapshot.get_entity(cow.id).await.unwrap().expect("found");
+    assert_eq!(frozen.get_value("animal_sound").await.unwrap(), Some("Moo".to_string()));
+
+    simulator.quiesce_and_stop().await;
+}
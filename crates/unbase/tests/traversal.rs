@@ -0,0 +1,91 @@
+use futures::TryStreamExt;
+use unbase::{
+    context::Context,
+    entity::Entity,
+    slab::EntityId,
+    traversal::{
+        Link,
+        Path,
+        Traversal,
+    },
+    util::simulator::Simulator,
+    Network,
+    Slab,
+};
+
+async fn visit(traversal: Traversal) -> Vec<(Path, EntityId)> {
+    let reached: Vec<(Path, Entity)> = traversal.stream().try_collect().await.unwrap();
+    reached.into_iter().map(|(path, entity)| (path, entity.id)).collect()
+}
+
+fn ends(visited: &[(Path, EntityId)]) -> Vec<EntityId> {
+    visited.iter()
+           .map(|(path, entity_id)| {
+               assert_eq!(path.end(), *entity_id);
+               *entity_id
+           })
+           .collect()
+}
+
+async fn node(context: &Context, name: &str) -> Entity {
+    Entity::new_with_single_kv(context, "name", name).await.unwrap()
+}
+
+#[unbase_test_util::async_test]
+async fn traversal_orders_and_filters() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    // a -0-> b -0-> c -1-> a, and a -1-> d
+    let mut a = node(&context_a, "a").await;
+    let mut b = node(&context_a, "b").await;
+    let mut c = node(&context_a, "c").await;
+    let d = node(&context_a, "d").await;
+
+    a.set_relation(0, &b).await.unwrap();
+    a.set_relation(1, &d).await.unwrap();
+    b.set_relation(0, &c).await.unwrap();
+    c.set_relation(1, &a).await.unwrap();
+
+    let visited = visit(context_a.traverse(&a)).await;
+    assert_eq!(ends(&visited), vec![a.id, b.id, d.id, c.id]);
+
+    // The cycle back to a is not followed, and paths report each hop
+    assert_eq!(visited[0].0.depth(), 0);
+    let to_c = &visited[3].0;
+    assert_eq!(to_c.start, a.id);
+    assert_eq!(to_c.hops.iter().map(|hop| (hop.link, hop.slot_id, hop.entity_id)).collect::<Vec<_>>(),
+               vec![(Link::Relation, 0, b.id), (Link::Relation, 0, c.id)]);
+
+    let visited = visit(context_a.traverse(&a).depth_first()).await;
+    assert_eq!(ends(&visited), vec![a.id, b.id, c.id, d.id]);
+
+    let visited = visit(context_a.traverse(&a).max_depth(1)).await;
+    assert_eq!(ends(&visited), vec![a.id, b.id, d.id]);
+
+    let visited = visit(context_a.traverse(&a).slots(&[0])).await;
+    assert_eq!(ends(&visited), vec![a.id, b.id, c.id]);
+
+    let visited = visit(context_a.traverse(&a).only(Link::Edge)).await;
+    assert_eq!(ends(&visited), vec![a.id]);
+
+    // A remote slab fetches the memos it lacks as it goes
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    let remote_a = context_b.get_entity(a.id).await.unwrap().expect("found");
+    let visited = visit(context_b.traverse(&remote_a)).await;
+    assert_eq!(ends(&visited), vec![a.id, b.id, d.id, c.id]);
+
+    simulator.quiesce_and_stop().await;
+}
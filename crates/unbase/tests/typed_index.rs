@@ -0,0 +1,127 @@
+use futures::TryStreamExt;
+use std::collections::HashMap;
+use unbase::{
+    head::Head,
+    schema::{
+        Schema,
+        ValueType,
+    },
+    slab::{
+        EdgeSet,
+        EntityId,
+        EntityType,
+        MemoBody,
+        RelationSet,
+    },
+    util::simulator::Simulator,
+    Entity,
+    Network,
+    Slab,
+};
+
+fn ids(entities: &[Entity]) -> Vec<EntityId> {
+    let mut ids: Vec<EntityId> = entities.iter().map(|entity| entity.id).collect();
+    ids.sort();
+    ids
+}
+
+#[unbase_test_util::async_test]
+async fn entities_of_type() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let simulator = Simulator::new();
+    net.add_transport(Box::new(simulator.clone()));
+
+    let slab_a = Slab::new(&net);
+    let slab_b = Slab::new(&net);
+    simulator.start();
+
+    let context_a = slab_a.create_context();
+    let context_b = slab_b.create_context();
+
+    let beast = Schema::new("Beast").field("sound", ValueType::String);
+    let plant = Schema::new("Plant").field("colour", ValueType::String);
+    context_a.register_schema(&beast).await.unwrap();
+    context_a.register_schema(&plant).await.unwrap();
+
+    // A registered type with no entities yet has an empty index
+    assert!(context_a.entities_of_type(EntityType::Custom(beast.type_id)).await.unwrap().is_empty());
+
+    let mut beasts = Vec::new();
+    for sound in ["Moo", "Baa", "Oink"].iter() {
+        let mut vals = HashMap::new();
+        vals.insert("sound".to_string(), sound.to_string());
+        beasts.push(Entity::new_typed(&context_a, beast.type_id, vals).await.unwrap());
+    }
+
+    let mut vals = HashMap::new();
+    vals.insert("colour".to_string(), "Green".to_string());
+    let fern = Entity::new_typed(&context_a, plant.type_id, vals).await.unwrap();
+
+    let record = Entity::new_with_single_kv(&context_a, "sound", "Moo").await.unwrap();
+
+    let found = context_a.entities_of_type(EntityType::Custom(beast.type_id)).await.unwrap();
+    assert_eq!(ids(&found), ids(&beasts));
+
+    let found = context_a.entities_of_type(EntityType::Custom(plant.type_id)).await.unwrap();
+    assert_eq!(ids(&found), vec![fern.id]);
+
+    let found = context_a.entities_of_type(EntityType::Record).await.unwrap();
+    assert_eq!(ids(&found), vec![record.id]);
+
+    // Unknown types have no index at all
+    assert!(context_a.entities_of_type(EntityType::Custom(12345)).await.unwrap().is_empty());
+
+    // Nor is the root of a type index mistaken for a record
+    let type_key = beast.type_id.to_string();
+    assert!(context_a.try_fetch_kv("type", &type_key).await.unwrap().is_none());
+
+    // Queries for a type draw their candidates from its index
+    let moo: Vec<Entity> = context_a.query()
+                                    .of_type(EntityType::Custom(beast.type_id))
+                                    .eq("sound", "Moo")
+                                    .stream()
+                                    .try_collect()
+                                    .await
+                                    .unwrap();
+    assert_eq!(ids(&moo), vec![beasts[0].id]);
+
+    // The per-type index replicates like any other
+    simulator.quiesce().await;
+    context_a.hack_send_context(&context_b).await.unwrap();
+
+    let found = context_b.entities_of_type(EntityType::Custom(beast.type_id)).await.unwrap();
+    assert_eq!(ids(&found), ids(&beasts));
+
+    simulator.quiesce_and_stop().await;
+}
+
+#[unbase_test_util::async_test]
+async fn entities_of_type_without_index() {
+    unbase_test_util::init_test_logger();
+
+    let net = Network::create_new_system();
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    // An entity of a type for which no index was ever declared, as written before type indexes existed
+    let stype = EntityType::Custom(77);
+    let entity_id = EntityId { id: 1234, stype };
+    let head = context_a.slab
+                        .new_memo(Some(entity_id),
+                                  Head::Null,
+                                  MemoBody::FullyMaterialized { v: HashMap::new(),
+                                                                r: RelationSet::empty(),
+                                                                e: EdgeSet::empty(),
+                                                                t: stype, })
+                        .to_head();
+    context_a.root_index().await.unwrap().insert(&context_a, entity_id.id, head).await.unwrap();
+
+    // Is found by scanning the root index instead
+    let found = context_a.entities_of_type(stype).await.unwrap();
+    assert_eq!(ids(&found), vec![entity_id]);
+
+    let queried: Vec<Entity> = context_a.query().of_type(stype).stream().try_collect().await.unwrap();
+    assert_eq!(ids(&queried), vec![entity_id]);
+}
@@ -3,6 +3,10 @@ use futures_await_test::async_test;
 use std::time::Duration;
 use timer::Delay;
 use unbase::{
+    blob::{
+        CHUNK_FANOUT,
+        CHUNK_SIZE,
+    },
     network::transport::TransportUDP,
     Entity,
     Network,
@@ -98,3 +102,72 @@ async fn test2_node_b() {
           beast_b.get_value("beast").await.expect("it worked").expect("has value"),
           beast_b.get_value("sound").await.expect("it worked").expect("has value"));
 }
+
+/// Enough chunks that the blob is gathered into ChunkTree memos
+fn blob_data() -> Vec<u8> {
+    (0..CHUNK_SIZE * (CHUNK_FANOUT + 1)).map(|i| (i % 253) as u8).collect()
+}
+
+#[unbase_test_util::async_test]
+async fn test_udp_blob() {
+    unbase_test_util::init_test_logger();
+
+    let t1 = test_blob_node_a();
+    let t2 = test_blob_node_b();
+
+    join! { t1, t2 };
+}
+
+async fn test_blob_node_a() {
+    let net = Network::create_new_system();
+    let udp = TransportUDP::new("127.0.0.1:53001".to_string());
+    net.add_transport(Box::new(udp));
+
+    let slab_a = Slab::new(&net);
+    let context_a = slab_a.create_context();
+
+    // HACK - wait for slab_b to be on the peer list, and to be hooked in to our root_index_seed
+    Delay::new(Duration::from_millis(150)).await;
+
+    let mut beast_a = Entity::new_with_single_kv(&context_a, "beast", "Tiger").await
+                                                                              .expect("write successful");
+    beast_a.set_blob("photo", &blob_data()).await.expect("write successful");
+
+    // Hang out so we can help task 2
+    Delay::new(Duration::from_millis(500)).await;
+}
+
+async fn test_blob_node_b() {
+    use futures::io::AsyncReadExt;
+
+    // HACK - Ensure slab_a is listening
+    Delay::new(Duration::from_millis(50)).await;
+
+    let net2 = Network::new();
+    net2.hack_set_next_slab_id(200);
+    let udp2 = TransportUDP::new("127.0.0.1:53002".to_string());
+    net2.add_transport(Box::new(udp2.clone()));
+    let slab_b = Slab::new(&net2);
+
+    udp2.seed_address_from_string("127.0.0.1:53001".to_string());
+    let context_b = slab_b.create_context();
+
+    let mut beast_b = context_b.fetch_kv("beast", "Tiger", Duration::from_secs(1))
+                               .await
+                               .expect("fetch_kv");
+
+    // The blob may not have arrived yet
+    let mut reader = None;
+    for _ in 0..20 {
+        reader = beast_b.get_blob("photo").await.expect("it worked");
+        if reader.is_some() {
+            break;
+        }
+        Delay::new(Duration::from_millis(50)).await;
+    }
+    let mut reader = reader.expect("blob arrived");
+
+    let mut photo = Vec::new();
+    reader.read_to_end(&mut photo).await.expect("read blob");
+    assert_eq!(photo, blob_data());
+}